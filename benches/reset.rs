@@ -0,0 +1,70 @@
+//! Cost of refilling drops when they reset, run with `cargo bench`.
+//!
+//! Compares rebuilding a group's code points for every drop, which is what resets
+//! used to do, against sampling a `GlyphTable` that was built once.
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rusty_rain::{Characters, GlyphTable, RainEngine, SpeedRange, UserSettings};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// A large terminal, every column resets once per round.
+const WIDTH: usize = 400;
+const HEIGHT: usize = 200;
+const ROUNDS: u32 = 20;
+
+fn time(name: &str, unit: &str, mut f: impl FnMut()) {
+    f();
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        f();
+    }
+    let per_round = start.elapsed() / ROUNDS;
+    println!("{name:<30} {per_round:>12.2?} per {unit}");
+}
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut columns = vec![vec![' '; HEIGHT + 1]; WIDTH];
+    for group in [Characters::Bin, Characters::Jap, Characters::All] {
+        time(
+            &format!("{group} rebuilt per drop"),
+            &format!("{WIDTH} resets"),
+            || {
+                for column in columns.iter_mut() {
+                    let g = group.as_vec_u32();
+                    *column = (0..=HEIGHT)
+                        .map(|_| char::from_u32(g[rng.gen_range(0..g.len())]).unwrap_or('#'))
+                        .collect();
+                }
+                black_box(&columns);
+            },
+        );
+        let table = GlyphTable::new(&group.into());
+        let mut ids = vec![vec![0; HEIGHT + 1]; WIDTH];
+        time(
+            &format!("{group} glyph table"),
+            &format!("{WIDTH} resets"),
+            || {
+                for column in ids.iter_mut() {
+                    table.fill(column, None, &mut rng);
+                }
+                black_box(&ids);
+            },
+        );
+    }
+
+    // The whole engine with drops fast enough that some column resets every step.
+    let settings = UserSettings::default()
+        .group(Characters::All)
+        .speed(SpeedRange::fixed(1).expect("1ms is a valid speed"))
+        .seed(Some(0));
+    let mut engine = RainEngine::new(settings, WIDTH as u16 * 2, HEIGHT as u16);
+    time("engine, all", &format!("{HEIGHT} steps"), || {
+        for _ in 0..HEIGHT {
+            let now = engine.now() + Duration::from_millis(1);
+            engine.step(now);
+        }
+        black_box(engine.rain());
+    });
+}
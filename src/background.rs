@@ -0,0 +1,32 @@
+use clap::ValueEnum;
+
+/// Color of the terminal behind the rain.
+#[derive(Debug, ValueEnum, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Background {
+    #[default]
+    Dark,
+    /// Colors have their lightness flipped so the head is dark and trails fade to white.
+    Light,
+}
+
+impl std::fmt::Display for Background {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            Self::Dark => "dark",
+            Self::Light => "light",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+impl Background {
+    /// Guesses from `COLORFGBG`, set by rxvt, Konsole and a few others as `fg;bg`.
+    pub fn from_env() -> Option<Self> {
+        let value = std::env::var("COLORFGBG").ok()?;
+        let bg = value.rsplit(';').next()?.parse::<u8>().ok()?;
+        match bg {
+            7 | 9..=15 => Some(Self::Light),
+            _ => Some(Self::Dark),
+        }
+    }
+}
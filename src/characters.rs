@@ -1,36 +1,130 @@
 use clap::ValueEnum;
 
-#[repr(u16)]
-#[derive(Debug, Clone, Copy)]
-pub enum CharWidth {
-    Single = 1,
-    Double = 2,
-}
+/// ISO 3166 country codes, each drawn as a flag from a pair of regional indicators.
+const COUNTRIES: &str = "\
+AD AE AF AG AI AL AM AO AQ AR AS AT AU AW AX AZ BA BB BD BE BF BG BH BI BJ BL BM BN BO BQ \
+BR BS BT BV BW BY BZ CA CC CD CF CG CH CI CK CL CM CN CO CR CU CV CW CX CY CZ DE DJ DK DM \
+DO DZ EC EE EG EH ER ES ET FI FJ FK FM FO FR GA GB GD GE GF GG GH GI GL GM GN GP GQ GR GS \
+GT GU GW GY HK HM HN HR HT HU ID IE IL IM IN IO IQ IR IS IT JE JM JO JP KE KG KH KI KM KN \
+KP KR KW KY KZ LA LB LC LI LK LR LS LT LU LV LY MA MC MD ME MF MG MH MK ML MM MN MO MP MQ \
+MR MS MT MU MV MW MX MY MZ NA NC NE NF NG NI NL NO NP NR NU NZ OM PA PE PF PG PH PK PL PM \
+PN PR PS PT PW PY QA RE RO RS RU RW SA SB SC SD SE SG SH SI SJ SK SL SM SN SO SR SS ST SV \
+SX SY SZ TC TD TF TG TH TJ TK TL TM TN TO TR TT TV TW TZ UA UG UM US UY UZ VA VC VE VG VI \
+VN VU WF WS YE YT ZA ZM ZW";
+
+/// No modifier followed by the five Fitzpatrick skin tones.
+const SKIN_TONES: [&str; 6] = [
+    "",
+    "\u{1F3FB}",
+    "\u{1F3FC}",
+    "\u{1F3FD}",
+    "\u{1F3FE}",
+    "\u{1F3FF}",
+];
+const PEOPLE: &str = "👶🧒👦👧🧑👨👩🧓👴👵🙋🙆🙅🤷🤦💁🙇";
+/// Joined onto 🧑, 👨 and 👩 with a zero width joiner, like 👩‍💻.
+const JOBS: &str = "💻🚀🔬🎤🌾🍳🚒🏫🎨🔧🏭💼🎓";
+const FAMILIES: [&str; 13] = [
+    "👨\u{200D}👩\u{200D}👦",
+    "👨\u{200D}👩\u{200D}👧",
+    "👨\u{200D}👩\u{200D}👧\u{200D}👦",
+    "👨\u{200D}👩\u{200D}👦\u{200D}👦",
+    "👨\u{200D}👩\u{200D}👧\u{200D}👧",
+    "👩\u{200D}👩\u{200D}👦",
+    "👩\u{200D}👩\u{200D}👧",
+    "👨\u{200D}👨\u{200D}👦",
+    "👨\u{200D}👨\u{200D}👧",
+    "👩\u{200D}👦",
+    "👩\u{200D}👧",
+    "👨\u{200D}👦",
+    "👨\u{200D}👧",
+];
 
-#[derive(Debug, ValueEnum, Clone, Copy)]
+/// The built in character groups, the doc comment of each is its `--help` text.
+#[derive(Debug, ValueEnum, Clone, Copy, PartialEq, Eq)]
 pub enum Characters {
+    /// This shows most of the Character Groups all at once
     All,
+    /// Lower Case Alphabet Characters
     Alphalow,
+    /// Upper Case Alphabet Characters
     Alphaup,
+    /// All Alphabets and Numbers
+    #[value(name = "alphanum", alias = "alpha-num")]
     AlphaNum,
+    /// Arrow Emojis or Fancy Characters
     Arrow,
+    /// All Ones and Zeros
     Bin,
+    /// ┼ Box drawing lines and corners
+    Box,
+    /// ⣿ Braille patterns
+    Braille,
+    /// Playing Cards
     Cards,
+    /// ♞ Chess pieces
+    Chess,
+    /// 🕑
     Clock,
+    /// 🦀
     Crab,
+    /// Ж Cyrillic letters
+    Cyrillic,
+    /// क Devanagari letters and digits
+    Devanagari,
+    /// ACGT
+    Dna,
+    /// 🀽
     Dominosh,
+    /// 🁫
     Dominosv,
+    /// 🌎
     Earth,
+    /// This is just a bunch of random Emojis
     Emojis,
+    /// 🇯🇵 Country flags
+    Flags,
+    /// Ω Greek letters
+    Greek,
+    /// 한 Korean syllables
+    Hangul,
+    /// 0-9 and A-F
+    Hex,
+    /// Japanese Characters
     Jap,
+    /// カ Full width Katakana
+    Katakana,
+    /// Cool Looking Large Letters
+    #[value(alias = "largeletters")]
     LargeLetters,
+    /// ∑ Mathematical operators
+    Math,
+    /// 🌕
     Moon,
+    /// ♫ Notes and musical symbols
+    Music,
+    /// Good ol fashion Numbers
     Num,
+    /// These are like pool balls
+    #[value(alias = "numberedballs")]
     NumberedBalls,
+    /// These are like the pool balls but just cubes
+    #[value(alias = "numberedcubes")]
     NumberedCubes,
+    /// 👩🏽‍💻 People and families in every skin tone
+    People,
+    /// Plants of sorts
     Plants,
+    /// ᚠ Runic letters
+    Runes,
+    /// 😃
     Smile,
+    /// Squares and Circles of a few colors
     Shapes,
+    /// ⛅ Sun, clouds, rain and snow
+    Weather,
+    /// ♈ Signs of the zodiac
+    Zodiac,
 }
 
 impl std::fmt::Display for Characters {
@@ -42,28 +136,85 @@ impl std::fmt::Display for Characters {
             Characters::AlphaNum => "alphanum",
             Characters::Arrow => "arrow",
             Characters::Bin => "bin",
+            Characters::Box => "box",
+            Characters::Braille => "braille",
             Characters::Cards => "cards",
+            Characters::Chess => "chess",
             Characters::Clock => "clock",
             Characters::Crab => "crab",
+            Characters::Cyrillic => "cyrillic",
+            Characters::Devanagari => "devanagari",
+            Characters::Dna => "dna",
             Characters::Dominosh => "dominosh",
             Characters::Dominosv => "dominosv",
             Characters::Earth => "earth",
             Characters::Emojis => "emojis",
+            Characters::Flags => "flags",
+            Characters::Greek => "greek",
+            Characters::Hangul => "hangul",
+            Characters::Hex => "hex",
             Characters::Jap => "jap",
-            Characters::LargeLetters => "largeletters",
+            Characters::Katakana => "katakana",
+            Characters::LargeLetters => "large-letters",
+            Characters::Math => "math",
             Characters::Moon => "moon",
+            Characters::Music => "music",
             Characters::Num => "num",
-            Characters::NumberedBalls => "numberedballs",
-            Characters::NumberedCubes => "numberedcubes",
+            Characters::NumberedBalls => "numbered-balls",
+            Characters::NumberedCubes => "numbered-cubes",
+            Characters::People => "people",
             Characters::Plants => "plants",
+            Characters::Runes => "runes",
             Characters::Smile => "smile",
             Characters::Shapes => "shapes",
+            Characters::Weather => "weather",
+            Characters::Zodiac => "zodiac",
         };
         write!(f, "{}", value)
     }
 }
 
 impl Characters {
+    /// Every glyph of the group. Most are a single character, `Flags` and `People`
+    /// are sequences of them.
+    pub fn glyphs(&self) -> Vec<String> {
+        match self {
+            Self::Flags => COUNTRIES
+                .split_whitespace()
+                .map(|code| {
+                    code.chars()
+                        .filter_map(|c| char::from_u32(0x1F1E6 + c as u32 - 'A' as u32))
+                        .collect()
+                })
+                .collect(),
+            Self::People => {
+                let people = PEOPLE.chars().map(String::from);
+                let jobs = "🧑👨👩".chars().flat_map(|person| {
+                    JOBS.chars()
+                        .map(move |job| (person.to_string(), format!("\u{200D}{job}")))
+                });
+                people
+                    .map(|person| (person, String::new()))
+                    .chain(jobs)
+                    .flat_map(|(person, job)| {
+                        SKIN_TONES
+                            .iter()
+                            .map(move |tone| format!("{person}{tone}{job}"))
+                    })
+                    .chain(FAMILIES.iter().map(|family| family.to_string()))
+                    .collect()
+            }
+            _ => self
+                .as_vec_u32()
+                .into_iter()
+                .filter_map(char::from_u32)
+                .map(String::from)
+                .collect(),
+        }
+    }
+
+    /// Code points of the groups made of single characters, `Flags` and `People` have
+    /// none, see `glyphs`.
     pub fn as_vec_u32(&self) -> Vec<u32> {
         match self {
             Self::All => Self::Alphalow
@@ -105,58 +256,57 @@ impl Characters {
                 .chain(129104..=129113)
                 .collect(),
             Self::Bin => (48..=49).collect(),
+            Self::Box => (9472..=9599).collect(),
+            Self::Braille => (10241..=10495).collect(),
             Self::Cards => (127137..=127166)
                 .chain(127169..=127182)
                 .chain(127185..=127198)
                 .collect(),
-            Self::Clock => (128336..=128359).collect(),
+            Self::Chess => (9812..=9823).collect(),
+            // Every hour then its half hour, so the hands go round in order.
+            Self::Clock => (128336..=128347)
+                .flat_map(|hour| [hour, hour + 12])
+                .collect(),
             Self::Crab => vec![129408],
+            Self::Cyrillic => (1040..=1103).collect(),
+            Self::Devanagari => (2309..=2361).chain(2406..=2415).collect(),
+            Self::Dna => vec![65, 67, 71, 84],
             Self::Dominosh => (127024..=127073).collect(),
             Self::Dominosv => (127074..=127123).collect(),
+            // Africa, the Americas then Asia, the way the face you see moves as it turns
+            // east, with the meridian globe last.
             Self::Earth => (127757..=127760).collect(),
+            Self::Flags | Self::People => Vec::new(),
             Self::Emojis => (129292..=129400) // Hearts
                 .chain(129402..=129482) // Diamonds
                 .chain(129484..=129535) // Clubs
                 // Spades?
                 .collect(),
+            Self::Greek => (913..=929).chain(931..=937).chain(945..=969).collect(),
+            Self::Hangul => (44032..=55203).collect(),
+            Self::Hex => (48..=57).chain(65..=70).collect(),
             Self::Jap => (65382..=65437).collect(),
-            Self::LargeLetters => (127462..=127487).collect(),
-            Self::Moon => (127760..=127773).collect(),
+            Self::Katakana => (12449..=12538).collect(),
+            // Fullwidth capitals, lone regional indicators only draw as boxes.
+            Self::LargeLetters => (65313..=65338).collect(),
+            Self::Math => (8704..=8959).collect(),
+            // The phases from new moon round to waning crescent, then the crescent and faces,
+            // with the meridian globe last.
+            Self::Moon => (127761..=127773).chain([127760]).collect(),
+            Self::Music => (9833..=9839).chain(119070..=119078).collect(),
             Self::Num => (48..=57).collect(),
             Self::NumberedBalls => (127312..=127337).collect(),
             Self::NumberedCubes => (127344..=127369).collect(),
             Self::Plants => (127793..=127827).collect(),
+            Self::Runes => (5792..=5866).collect(),
             Self::Smile => (128512..=128518).collect(),
             Self::Shapes => (128992..=129003).collect(),
-        }
-    }
-}
-
-impl Characters {
-    pub fn width(&self) -> u16 {
-        match self {
-            Self::All => CharWidth::Double as u16,
-            Self::Alphalow => CharWidth::Single as u16,
-            Self::Alphaup => CharWidth::Single as u16,
-            Self::AlphaNum => CharWidth::Single as u16,
-            Self::Arrow => CharWidth::Double as u16,
-            Self::Bin => CharWidth::Single as u16,
-            Self::Cards => CharWidth::Double as u16,
-            Self::Clock => CharWidth::Double as u16,
-            Self::Crab => CharWidth::Double as u16,
-            Self::Dominosh => CharWidth::Double as u16,
-            Self::Dominosv => CharWidth::Single as u16,
-            Self::Earth => CharWidth::Double as u16,
-            Self::Emojis => CharWidth::Double as u16,
-            Self::Jap => CharWidth::Single as u16,
-            Self::LargeLetters => CharWidth::Double as u16,
-            Self::Moon => CharWidth::Double as u16,
-            Self::Num => CharWidth::Single as u16,
-            Self::NumberedBalls => CharWidth::Double as u16,
-            Self::NumberedCubes => CharWidth::Double as u16,
-            Self::Plants => CharWidth::Double as u16,
-            Self::Smile => CharWidth::Double as u16,
-            Self::Shapes => CharWidth::Double as u16,
+            Self::Weather => (9728..=9732) // Sun, cloud, umbrella, snowman and comet
+                .chain([9748, 9889, 9924, 9925, 9928, 10052])
+                .chain([127744, 127746, 127752])
+                .chain(127780..=127788)
+                .collect(),
+            Self::Zodiac => (9800..=9811).chain([9934]).collect(),
         }
     }
 }
@@ -0,0 +1,499 @@
+use crate::glyphs::glyph_width;
+use crate::Characters;
+use clap::ValueEnum;
+use std::collections::HashSet;
+use std::path::Path;
+use unicode_script::{Script, UnicodeScript};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// The glyphs rain is made from, a built in group, a weighted mix of them or one the
+/// user put together.
+#[derive(Debug, Clone)]
+pub enum CharacterSet {
+    Builtin(Characters),
+    Mix(Mix),
+    Custom(CustomChars),
+}
+
+impl CharacterSet {
+    /// Glyphs of each group in the set along with its weight.
+    pub fn parts(&self) -> Vec<(Vec<String>, u32)> {
+        match self {
+            Self::Builtin(group) => vec![(group.glyphs(), 1)],
+            Self::Mix(mix) => mix
+                .parts()
+                .iter()
+                .map(|(group, weight)| (group.glyphs(), *weight))
+                .collect(),
+            Self::Custom(custom) => vec![(custom.glyphs().to_vec(), 1)],
+        }
+    }
+}
+
+impl From<Characters> for CharacterSet {
+    fn from(group: Characters) -> Self {
+        Self::Builtin(group)
+    }
+}
+
+impl From<Mix> for CharacterSet {
+    fn from(mix: Mix) -> Self {
+        match mix.parts() {
+            [(group, _)] => Self::Builtin(*group),
+            _ => Self::Mix(mix),
+        }
+    }
+}
+
+impl From<CustomChars> for CharacterSet {
+    fn from(custom: CustomChars) -> Self {
+        Self::Custom(custom)
+    }
+}
+
+impl std::fmt::Display for CharacterSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Builtin(group) => write!(f, "{}", group),
+            Self::Mix(mix) => write!(f, "{}", mix),
+            Self::Custom(custom) => write!(f, "{}", custom),
+        }
+    }
+}
+
+/// Built in groups mixed in proportion to their weights, written `jap:70,num:25,alphaup:5`.
+///
+/// A group without a weight counts as `1`, so `jap` on its own is just that group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mix {
+    parts: Vec<(Characters, u32)>,
+}
+
+impl Mix {
+    pub fn parts(&self) -> &[(Characters, u32)] {
+        &self.parts
+    }
+}
+
+impl From<Characters> for Mix {
+    fn from(group: Characters) -> Self {
+        Self {
+            parts: vec![(group, 1)],
+        }
+    }
+}
+
+impl std::fmt::Display for Mix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let parts = self.parts.iter().map(|(group, weight)| {
+            let name = group
+                .to_possible_value()
+                .map(|v| v.get_name().to_string())
+                .unwrap_or_else(|| group.to_string());
+            match (self.parts.len(), weight) {
+                (1, 1) => name,
+                _ => format!("{name}:{weight}"),
+            }
+        });
+        write!(f, "{}", parts.collect::<Vec<_>>().join(","))
+    }
+}
+
+impl std::str::FromStr for Mix {
+    type Err = ParseCharsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts = s
+            .split(',')
+            .map(|part| {
+                let (name, weight) = match part.split_once(':') {
+                    Some((name, weight)) => (name.trim(), Some(weight.trim())),
+                    None => (part.trim(), None),
+                };
+                let group = Characters::from_str(name, true)
+                    .map_err(|_| ParseCharsError::UnknownGroup(name.into()))?;
+                let weight = match weight {
+                    Some(weight) => weight
+                        .parse::<u32>()
+                        .ok()
+                        .filter(|&w| w > 0)
+                        .ok_or_else(|| ParseCharsError::BadWeight(weight.into()))?,
+                    None => 1,
+                };
+                Ok((group, weight))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        // Groups are picked by a `WeightedIndex`, which adds the weights up as `u32`.
+        parts.iter().try_fold(0u32, |total, (_, weight)| {
+            total
+                .checked_add(*weight)
+                .ok_or_else(|| ParseCharsError::BadWeight(weight.to_string()))
+        })?;
+        Ok(Self { parts })
+    }
+}
+
+/// A character set described by the user.
+///
+/// Each spec is one of
+/// - `U+30A0..U+30FF`, an inclusive range of code points, or a single `U+2603`
+/// - `block:Katakana`, every assigned glyph of a Unicode block
+/// - `script:Greek`, every glyph of a Unicode script
+/// - anything else is taken as the literal glyphs to use, like `01ABCDEF`, split into
+///   grapheme clusters so `🇯🇵` or `👩🏽‍💻` stay whole
+///
+/// Whitespace, control characters and zero width marks are left out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomChars {
+    spec: String,
+    glyphs: Vec<String>,
+}
+
+impl CustomChars {
+    /// Reads one spec per line, blank lines are skipped.
+    pub fn from_file(path: &Path) -> Result<Self, ParseCharsError> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| ParseCharsError::File(format!("{}: {e}", path.display())))?;
+        let sets = text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(str::parse)
+            .collect::<Result<Vec<Self>, _>>()?;
+        Self::join(sets).ok_or_else(|| ParseCharsError::Empty(path.display().to_string()))
+    }
+
+    /// Merges sets into one, glyphs appearing in more than one set are only kept once.
+    pub fn join(sets: impl IntoIterator<Item = Self>) -> Option<Self> {
+        sets.into_iter().reduce(|mut joined, set| {
+            let mut seen: HashSet<String> = joined.glyphs.iter().cloned().collect();
+            joined.spec = format!("{} {}", joined.spec, set.spec);
+            joined.glyphs.extend(
+                set.glyphs
+                    .into_iter()
+                    .filter(|glyph| seen.insert(glyph.clone())),
+            );
+            joined
+        })
+    }
+
+    pub fn glyphs(&self) -> &[String] {
+        &self.glyphs
+    }
+
+    /// Number of glyphs in the set.
+    pub fn len(&self) -> usize {
+        self.glyphs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.glyphs.is_empty()
+    }
+}
+
+impl std::fmt::Display for CustomChars {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.spec)
+    }
+}
+
+impl std::str::FromStr for CustomChars {
+    type Err = ParseCharsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let spec = s.trim();
+        let glyphs: Vec<String> = if let Some(name) = strip_prefix(spec, "block:") {
+            block(name)?.into_iter().map(String::from).collect()
+        } else if let Some(name) = strip_prefix(spec, "script:") {
+            script(name)?.into_iter().map(String::from).collect()
+        } else if let Some(range) = code_point_range(spec) {
+            range?.into_iter().map(String::from).collect()
+        } else {
+            spec.graphemes(true).map(String::from).collect()
+        };
+        let mut seen = HashSet::new();
+        let glyphs: Vec<String> = glyphs
+            .into_iter()
+            .filter(|glyph| printable(glyph) && seen.insert(glyph.clone()))
+            .collect();
+        if glyphs.is_empty() {
+            return Err(ParseCharsError::Empty(spec.into()));
+        }
+        Ok(Self {
+            spec: spec.into(),
+            glyphs,
+        })
+    }
+}
+
+/// Why a custom character set could not be built.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseCharsError {
+    Empty(String),
+    BadRange(String),
+    UnknownBlock(String),
+    UnknownScript(String),
+    File(String),
+    UnknownGroup(String),
+    BadWeight(String),
+}
+
+impl std::fmt::Display for ParseCharsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Empty(spec) => write!(f, "'{spec}' has no printable characters"),
+            Self::BadRange(spec) => write!(
+                f,
+                "'{spec}' is not a code point range, expected something like U+30A0..U+30FF"
+            ),
+            Self::UnknownBlock(name) => write!(
+                f,
+                "no Unicode block named '{name}', try names like Katakana or Box Drawing"
+            ),
+            Self::UnknownScript(name) => write!(
+                f,
+                "no Unicode script named '{name}', try names like Greek, Han or Cyrl"
+            ),
+            Self::File(e) => write!(f, "{e}"),
+            Self::UnknownGroup(name) => write!(f, "no character group named '{name}'"),
+            Self::BadWeight(weight) => {
+                write!(
+                    f,
+                    "weight '{weight}' must be a whole number above 0 and all weights must add up to at most {}",
+                    u32::MAX
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseCharsError {}
+
+fn strip_prefix<'a>(spec: &'a str, prefix: &str) -> Option<&'a str> {
+    spec.get(..prefix.len())
+        .filter(|head| head.eq_ignore_ascii_case(prefix))
+        .map(|_| spec[prefix.len()..].trim())
+}
+
+fn printable(glyph: &str) -> bool {
+    !glyph.chars().all(char::is_whitespace) && glyph_width(glyph) > 0
+}
+
+/// `None` when `spec` doesn't start like a code point, so it is read as literal glyphs.
+fn code_point_range(spec: &str) -> Option<Result<Vec<char>, ParseCharsError>> {
+    let rest = strip_prefix(spec, "U+")?;
+    if !rest.starts_with(|c: char| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let bad = || ParseCharsError::BadRange(spec.into());
+    let point = |s: &str| {
+        let s = s.trim();
+        let s = strip_prefix(s, "U+").unwrap_or(s);
+        u32::from_str_radix(s, 16).map_err(|_| bad())
+    };
+    let (start, end) = match rest.split_once("..=").or_else(|| rest.split_once("..")) {
+        Some((start, end)) => (point(start), point(end)),
+        None => match rest.split_once('-') {
+            Some((start, end)) => (point(start), point(end)),
+            None => (point(rest), point(rest)),
+        },
+    };
+    let range = start
+        .and_then(|start| end.map(|end| start..=end))
+        .and_then(|range| match range.is_empty() || *range.end() > 0x10FFFF {
+            true => Err(bad()),
+            false => Ok(range),
+        });
+    Some(range.map(|range| range.filter_map(char::from_u32).collect()))
+}
+
+/// Compares names ignoring case, spaces, dashes and underscores.
+fn loose_eq(a: &str, b: &str) -> bool {
+    let key = |s: &str| {
+        s.chars()
+            .filter(|c| c.is_alphanumeric())
+            .flat_map(char::to_lowercase)
+            .collect::<String>()
+    };
+    key(a) == key(b)
+}
+
+fn block(name: &str) -> Result<Vec<char>, ParseCharsError> {
+    // The crate has no list of blocks, so walk them from the bottom of the code space.
+    let mut point = 0u32;
+    while point <= 0x10FFFF {
+        let found = char::from_u32(point).and_then(unicode_blocks::find_unicode_block);
+        match found {
+            Some(block) if loose_eq(block.name(), name) => {
+                return Ok((block.start()..=block.end())
+                    .filter_map(char::from_u32)
+                    // Holes in a block are unassigned and show up as boxes.
+                    .filter(|c| c.script() != Script::Unknown)
+                    .collect());
+            }
+            Some(block) => point = block.end() + 1,
+            None => point += 1,
+        }
+    }
+    Err(ParseCharsError::UnknownBlock(name.into()))
+}
+
+fn script(name: &str) -> Result<Vec<char>, ParseCharsError> {
+    let script = Script::from_full_name(name)
+        .or_else(|| Script::from_full_name(&name.replace([' ', '-'], "_")))
+        .or_else(|| Script::from_short_name(name))
+        .or_else(|| {
+            let mut chars = name.chars();
+            let title = chars
+                .next()
+                .map(|first| {
+                    first
+                        .to_uppercase()
+                        .chain(chars.flat_map(char::to_lowercase))
+                })?
+                .collect::<String>();
+            Script::from_full_name(&title.replace([' ', '-'], "_"))
+                .or_else(|| Script::from_short_name(&title))
+        })
+        .filter(|&script| script != Script::Unknown)
+        .ok_or_else(|| ParseCharsError::UnknownScript(name.into()))?;
+    Ok(('\0'..=char::MAX)
+        .filter(|c| c.script() == script)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CustomChars, Mix, ParseCharsError};
+    use crate::Characters;
+
+    #[test]
+    fn parses_weighted_mixes() {
+        let cases = [
+            ("jap", vec![(Characters::Jap, 1)]),
+            (
+                "jap:70,num:25,alphaup:5",
+                vec![
+                    (Characters::Jap, 70),
+                    (Characters::Num, 25),
+                    (Characters::Alphaup, 5),
+                ],
+            ),
+            (
+                " Jap : 3 , num ",
+                vec![(Characters::Jap, 3), (Characters::Num, 1)],
+            ),
+            ("alpha-num:2", vec![(Characters::AlphaNum, 2)]),
+            (
+                "bin:4294967294,num:1",
+                vec![(Characters::Bin, u32::MAX - 1), (Characters::Num, 1)],
+            ),
+        ];
+        for (input, parts) in cases {
+            let mix = input.parse::<Mix>();
+            assert_eq!(mix.as_ref().map(Mix::parts), Ok(&parts[..]), "{input}");
+        }
+    }
+
+    #[test]
+    fn rejects_bad_mixes() {
+        let cases = [
+            ("jap:0", ParseCharsError::BadWeight("0".into())),
+            ("jap:-1", ParseCharsError::BadWeight("-1".into())),
+            ("jap:lots", ParseCharsError::BadWeight("lots".into())),
+            ("jap:", ParseCharsError::BadWeight("".into())),
+            (
+                "jap:4294967295,num:4294967295",
+                ParseCharsError::BadWeight("4294967295".into()),
+            ),
+            ("kana:5", ParseCharsError::UnknownGroup("kana".into())),
+            ("jap,,num", ParseCharsError::UnknownGroup("".into())),
+        ];
+        for (input, error) in cases {
+            assert_eq!(input.parse::<Mix>(), Err(error), "{input}");
+        }
+    }
+
+    #[test]
+    fn displays_what_it_parses() {
+        for input in [
+            "jap",
+            "jap:70,num:25,alphaup:5",
+            "alphanum:2",
+            "bin:1,num:1",
+        ] {
+            assert_eq!(input.parse::<Mix>().unwrap().to_string(), input);
+        }
+    }
+
+    #[test]
+    fn parses_custom_specs() {
+        let cases = [
+            ("01ABCDEF", "01ABCDEF"),
+            (" aab a ", "ab"),
+            ("🇯🇵👩🏽‍💻", "🇯🇵👩🏽‍💻"),
+            ("U+2603", "☃"),
+            ("u+30..U+39", "0123456789"),
+            ("U+0030..=U+0039", "0123456789"),
+            ("U+30-39", "0123456789"),
+            ("U+0041..0046", "ABCDEF"),
+        ];
+        for (input, glyphs) in cases {
+            let custom = input.parse::<CustomChars>();
+            assert_eq!(
+                custom.map(|custom| custom.glyphs().concat()),
+                Ok(glyphs.to_string()),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn parses_blocks_and_scripts() {
+        let cases = [
+            ("block:Box Drawing", Some(128), '┼', 'A'),
+            ("block:box-drawing", Some(128), '╬', '0'),
+            ("BLOCK:Katakana", Some(96), 'カ', 'か'),
+            ("script:Greek", None, 'Ω', 'A'),
+            ("script:cyrl", None, 'Ж', 'Ω'),
+        ];
+        for (input, len, inside, outside) in cases {
+            let custom: CustomChars = input.parse().unwrap();
+            if let Some(len) = len {
+                assert_eq!(custom.len(), len, "{input}");
+            }
+            assert!(custom.glyphs().contains(&inside.to_string()), "{input}");
+            assert!(!custom.glyphs().contains(&outside.to_string()), "{input}");
+        }
+    }
+
+    #[test]
+    fn rejects_bad_specs() {
+        let cases = [
+            ("   ", ParseCharsError::Empty("".into())),
+            ("U+0300", ParseCharsError::Empty("U+0300".into())),
+            ("U+39..U+30", ParseCharsError::BadRange("U+39..U+30".into())),
+            ("U+30..zz", ParseCharsError::BadRange("U+30..zz".into())),
+            ("U+110000", ParseCharsError::BadRange("U+110000".into())),
+            (
+                "block:Nowhere",
+                ParseCharsError::UnknownBlock("Nowhere".into()),
+            ),
+            (
+                "script:Klingon",
+                ParseCharsError::UnknownScript("Klingon".into()),
+            ),
+        ];
+        for (input, error) in cases {
+            assert_eq!(input.parse::<CustomChars>(), Err(error), "{input}");
+        }
+    }
+
+    #[test]
+    fn joins_sets_keeping_each_glyph_once() {
+        let sets = ["abc", "U+62..U+64", "xa"].map(|spec| spec.parse::<CustomChars>().unwrap());
+        let joined = CustomChars::join(sets).unwrap();
+        assert_eq!(joined.glyphs().concat(), "abcdx");
+        assert_eq!(joined.to_string(), "abc U+62..U+64 xa");
+        assert_eq!(CustomChars::join([]), None);
+    }
+}
@@ -1,7 +1,18 @@
-use super::{AUTHOR, MAXSPEED, MINSPEED};
-use crate::characters::Characters;
-use crate::direction::Direction;
-use clap::{crate_description, crate_name, crate_version, Parser};
+use super::AUTHOR;
+use crate::theme::Theme;
+use crate::{config, probe};
+use clap::parser::ValueSource;
+use clap::{
+    crate_description, crate_name, crate_version, ArgMatches, CommandFactory, FromArgMatches,
+    Parser, ValueEnum,
+};
+use rusty_rain::{
+    Background, CharacterSet, Characters, Color, ColorDepth, CustomChars, Direction, Easing,
+    Gradient, LengthRange, Mix, SpeedDist, SpeedRange, UserSettings,
+};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
 
 const HELP_DIRECTION: &str = "Set the direction of the Rain.
 Default is set to down/south
@@ -12,50 +23,67 @@ OPTIONS:
     right, east
 ";
 
-const HELP_COLORS: &str = "Set color of Rain with color string name or tuple
+const HELP_COLORS: &str = "Set color of Rain.
 OPTIONS:
-    white,
-    red,
-    blue,
-    green,
-    r,g,b
+    green, darkcyan, ... - Any CSS/X11 color name
+    #00ff41, #0f4        - Hex
+    0,255,65             - r,g,b also written rgb(0,255,65)
+    hsl(120,100%,50%)    - Hue, saturation and lightness
+    ansi:46              - Index into the 256 color palette
 ";
 
-const HELP_CHARS: &str = "Set what kind of characters are printed as rain.
+const HELP_CHARS_CUSTOM: &str = "Use your own characters instead of --chars, can be repeated.
 OPTIONS:
-    all            - This shows most of the Character Groups all at once.
-    alphalow       - Lower Case Alphabet Characters
-    alphaup        - Upper Case Alphabet Characters
-    arrow          - Arrow Emojis or Fancy Characters
-    bin            - All Ones and Zeros
-    cards          - Playing Cards
-    clock          - 🕑
-    crab           - 🦀
-    dominosh       - 🀽
-    dominosv       - 🁫
-    earth          - 🌎
-    emojis         - This is just a bunch of random Emojis
-    jap            - Japanese Characters
-    large-letters  - Cool Looking Large Letters
-    moon           - 🌕
-    num            - Good ol fashion Numbers
-    numbered-balls - These are like pool balls
-    numbered-cubes - These are like the pool balls but just cubes
-    plants         - Plants of sorts
-    smile          - 😃
-    shapes         - Squares and Circles of a few colors
+    01ABCDEF        - The characters themselves
+    U+30A0..U+30FF  - A range of code points, or one like U+2603
+    block:Katakana  - Every character in a Unicode block
+    script:Greek    - Every character in a Unicode script
 ";
 
-const HELP_HEAD: &str = "Set the color of the first char in Rain.
+const HELP_SPEED: &str = "Set the delay in milliseconds between each step of a drop.
+OPTIONS:
+    fast, normal, slow - Presets
+    80..200            - Shortest and longest delay, also written 80,200
+    100                - Every drop moves at the same speed
+";
+
+const HELP_LENGTH: &str = "Set how many rows long the trails are.
+Default is from 4 up to 10 rows short of the screen.
 OPTIONS:
-    white,
-    red,
-    blue,
-    green,
-    r,g,b
+    4,20     - Shortest and longest trail in rows, also written 4..20
+    0.1,0.5  - Fractions of the screen height, which can be mixed with rows
+    12       - Every trail is the same length
 ";
 
-#[derive(Debug, Parser)]
+const HELP_GRADIENT: &str = "Color the whole drop with a gradient of any number of stops.
+The first stop colors the head, --color and --head are ignored.
+Stops take the same forms as --color, use rgb(r,g,b) instead of r,g,b.
+    -g \"#fff,#0f0,#003300\"
+";
+
+const HELP_HEAD: &str = "Set the color of the first char in Rain.
+Takes the same forms as --color.
+";
+
+#[derive(Debug, ValueEnum, Clone, Copy, PartialEq, Eq)]
+pub enum BackgroundArg {
+    Auto,
+    Dark,
+    Light,
+}
+
+impl std::fmt::Display for BackgroundArg {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            Self::Auto => "auto",
+            Self::Dark => "dark",
+            Self::Light => "light",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+#[derive(Debug, Clone, Parser)]
 #[command(
     author = AUTHOR,
     about = "A cross platform matrix rain made with Rust.",
@@ -64,81 +92,365 @@ OPTIONS:
     name = crate_name!(),
     version = crate_version!())]
 pub struct Cli {
-    #[arg(short, long, default_value_t = false)]
+    #[arg(short, long, env = "RUSTY_RAIN_SHADE", default_value_t = false)]
     pub shade: bool,
-    #[arg(short, long, help = HELP_CHARS, default_value_t = Characters::Bin)]
-    pub chars: Characters,
-    #[arg(short = 'C', long, help = HELP_COLORS, default_value_t = String::from("green"))]
-    pub color: String,
-    #[arg(short = 'H', long, help = HELP_HEAD, default_value_t = String::from("white"))]
-    pub head: String,
-    #[arg(short, long, help = HELP_DIRECTION, default_value_t = Direction::Down)]
+    #[arg(short, long, env = "RUSTY_RAIN_CHARS", help = help_chars(), default_value = "bin")]
+    pub chars: Mix,
+    #[arg(
+        long,
+        env = "RUSTY_RAIN_PER_COLUMN",
+        help = "Give each column one group of the --chars mix instead of mixing every glyph"
+    )]
+    pub per_column: bool,
+    #[arg(
+        long,
+        env = "RUSTY_RAIN_SEQUENCE",
+        help = "Step each drop through its group in order, moon phases wax and wane and clocks tick"
+    )]
+    pub sequence: bool,
+    #[arg(
+        long,
+        env = "RUSTY_RAIN_CHARS_CUSTOM",
+        value_name = "SPEC",
+        help = HELP_CHARS_CUSTOM
+    )]
+    pub chars_custom: Vec<CustomChars>,
+    #[arg(
+        long,
+        env = "RUSTY_RAIN_CHARS_FILE",
+        value_name = "PATH",
+        help = "Read --chars-custom specs from PATH, one per line"
+    )]
+    pub chars_file: Option<PathBuf>,
+    /// `--chars-custom` and `--chars-file` put together once everything is loaded.
+    #[arg(skip)]
+    pub custom: Option<CustomChars>,
+    #[arg(short = 'C', long, env = "RUSTY_RAIN_COLOR", help = HELP_COLORS, default_value = "green")]
+    pub color: Color,
+    #[arg(short = 'H', long, env = "RUSTY_RAIN_HEAD", help = HELP_HEAD, default_value = "white")]
+    pub head: Color,
+    #[arg(short, long, env = "RUSTY_RAIN_DIRECTION", help = HELP_DIRECTION, default_value_t = Direction::Down)]
     pub direction: Direction,
-    #[arg(short = 'S', long, default_value_t = format!("{MAXSPEED},{MINSPEED}"))]
-    pub speed: String,
+    #[arg(short = 'S', long, env = "RUSTY_RAIN_SPEED", help = HELP_SPEED, default_value_t = SpeedRange::default())]
+    pub speed: SpeedRange,
+    #[arg(
+        long,
+        env = "RUSTY_RAIN_SPEED_DIST",
+        default_value_t = SpeedDist::Uniform,
+        help = "How delays are spread over the --speed range"
+    )]
+    pub speed_dist: SpeedDist,
+    #[arg(
+        long,
+        env = "RUSTY_RAIN_GRAVITY",
+        value_parser = parse_gravity,
+        default_value_t = 0.0,
+        help = "Speed drops up as they fall, at 0.05 they move twice as fast after 20 rows"
+    )]
+    pub gravity: f64,
+    #[arg(
+        long,
+        env = "RUSTY_RAIN_JITTER",
+        value_name = "RATE",
+        value_parser = parse_rate,
+        default_value_t = 0.0,
+        help = "Stretch or shrink each step of a drop at random by up to RATE, from 0 to 1"
+    )]
+    pub jitter: f64,
+    #[arg(long, env = "RUSTY_RAIN_LENGTH", value_name = "MIN,MAX", help = HELP_LENGTH)]
+    pub length: Option<LengthRange>,
+    #[arg(
+        short,
+        long,
+        env = "RUSTY_RAIN_GRADIENT",
+        value_name = "COLORS",
+        help = HELP_GRADIENT
+    )]
+    pub gradient: Option<Gradient>,
+    #[arg(
+        short,
+        long,
+        env = "RUSTY_RAIN_EASING",
+        default_value_t = Easing::Linear,
+        help = "How quickly trails move through their gradient or shading"
+    )]
+    pub easing: Easing,
+    #[arg(
+        long,
+        env = "RUSTY_RAIN_RAINBOW",
+        help = "Give each column its own hue spread around the color wheel"
+    )]
+    pub rainbow: bool,
+    #[arg(
+        long,
+        env = "RUSTY_RAIN_HUE_CYCLE",
+        value_name = "SECONDS",
+        value_parser = parse_seconds,
+        help = "Turn every color once around the color wheel every SECONDS"
+    )]
+    pub hue_cycle: Option<f64>,
+    #[arg(
+        long,
+        env = "RUSTY_RAIN_COLOR_DEPTH",
+        value_name = "DEPTH",
+        help = "Colors the terminal can show [default: detected from NO_COLOR, COLORTERM and TERM]"
+    )]
+    pub color_depth: Option<ColorDepth>,
+    #[arg(
+        long,
+        env = "RUSTY_RAIN_DITHER",
+        help = "Dither trails when the color depth is less than truecolor"
+    )]
+    pub dither: bool,
+    #[arg(
+        long,
+        env = "RUSTY_RAIN_BACKGROUND",
+        default_value_t = BackgroundArg::Auto,
+        help = "Terminal background, auto asks the terminal and falls back to dark"
+    )]
+    pub background: BackgroundArg,
+    #[arg(
+        short,
+        long,
+        env = "RUSTY_RAIN_MUTATE",
+        value_name = "RATE",
+        value_parser = parse_rate,
+        default_value_t = 0.0,
+        help = "Chance from 0 to 1 that each visible glyph changes every step"
+    )]
+    pub mutate: f64,
+    #[arg(
+        long,
+        env = "RUSTY_RAIN_DENSITY",
+        value_name = "DENSITY",
+        value_parser = parse_density,
+        help = "Let columns hold several drops, from sparse at 0.1 to a downpour at 1 [default: one drop per column]"
+    )]
+    pub density: Option<f64>,
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Config file to load [default: $XDG_CONFIG_HOME/rusty-rain/config.toml]"
+    )]
+    pub config: Option<PathBuf>,
+    #[arg(
+        short,
+        long,
+        env = "RUSTY_RAIN_PROFILE",
+        help = "Use the [profile.<PROFILE>] section of the config file"
+    )]
+    pub profile: Option<String>,
+    #[arg(
+        short,
+        long,
+        env = "RUSTY_RAIN_THEME",
+        help = "Preset colors, characters, shading and speed, see --list-themes"
+    )]
+    pub theme: Option<Theme>,
+    #[arg(long, help = "List the available themes and exit")]
+    pub list_themes: bool,
+    #[arg(
+        long,
+        help = "List every character group with a sample of its glyphs and exit"
+    )]
+    pub list_chars: bool,
+    #[arg(
+        long,
+        value_name = "GROUP",
+        help = "Rain GROUP in a small part of the screen for a few seconds and exit"
+    )]
+    pub preview: Option<Mix>,
+    #[arg(long, help = "Print the effective settings as TOML and exit")]
+    pub print_config: bool,
+    #[arg(long, help = "Seed the random number generator to repeat a run")]
+    pub seed: Option<u64>,
+    #[arg(
+        long,
+        value_name = "MS",
+        help = "Advance a fixed clock by MS every frame instead of reading the system clock"
+    )]
+    pub fixed_clock: Option<u64>,
+    #[arg(
+        long,
+        help = "Simulate without a terminal and print every frame to stdout"
+    )]
+    pub headless: bool,
+    #[arg(
+        long,
+        value_name = "WIDTHxHEIGHT",
+        value_parser = parse_size,
+        requires = "headless",
+        help = "Size of the virtual screen in headless mode [default: terminal size or 80x24]"
+    )]
+    pub size: Option<(u16, u16)>,
+    #[arg(
+        long,
+        default_value_t = 100,
+        requires = "headless",
+        help = "Number of frames to simulate in headless mode"
+    )]
+    pub frames: u64,
 }
 
 impl Cli {
-    pub fn rain_color(&self) -> (u8, u8, u8) {
-        into_color(&self.color)
-    }
-    pub fn head_color(&self) -> (u8, u8, u8) {
-        into_color(&self.head)
+    /// Parses the command line and fills in anything not given from the config file.
+    pub fn load() -> Self {
+        let matches = Self::command().get_matches();
+        let mut cli = Self::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+        if let Err(e) = cli.pick_chars(&matches) {
+            Self::command()
+                .error(clap::error::ErrorKind::ArgumentConflict, e)
+                .exit();
+        }
+        if let Err(e) = config::apply(&mut cli, &matches).and_then(|_| cli.load_custom()) {
+            Self::command()
+                .error(clap::error::ErrorKind::InvalidValue, e)
+                .exit();
+        }
+        cli
     }
 
-    pub fn speed(&self) -> (u64, u64) {
-        match self.speed.into_tuple() {
-            Ok((max, min)) => (max, min),
-            _ => (MAXSPEED, MINSPEED),
+    /// `--chars` can't be given along with custom characters, but either one on the
+    /// command line beats the other coming from the environment.
+    fn pick_chars(&mut self, matches: &ArgMatches) -> Result<(), String> {
+        let given = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+        if !given("chars") {
+            return Ok(());
         }
+        for (id, flag) in [
+            ("chars_custom", "--chars-custom"),
+            ("chars_file", "--chars-file"),
+        ] {
+            if given(id) {
+                return Err(format!(
+                    "the argument '--chars' cannot be used with '{flag}'"
+                ));
+            }
+        }
+        self.chars_custom.clear();
+        self.chars_file = None;
+        Ok(())
+    }
+
+    fn load_custom(&mut self) -> Result<(), String> {
+        let file = match &self.chars_file {
+            Some(path) => {
+                Some(CustomChars::from_file(path).map_err(|e| format!("chars-file: {e}"))?)
+            }
+            None => None,
+        };
+        self.custom = CustomChars::join(self.chars_custom.iter().cloned().chain(file));
+        Ok(())
+    }
+
+    pub fn user_settings(&self) -> UserSettings {
+        let group = match &self.custom {
+            Some(custom) => CharacterSet::Custom(custom.clone()),
+            None => self.chars.clone().into(),
+        };
+        // Headless frames don't depend on whatever terminal happens to be around.
+        let (group, widths) = match self.headless {
+            true => (group, HashMap::new()),
+            false => probe::glyphs(group),
+        };
+        UserSettings::default()
+            .rain_color(self.color)
+            .head_color(self.head)
+            .group(group)
+            .glyph_widths(widths)
+            .per_column(self.per_column)
+            .sequence(self.sequence)
+            .shading(self.shade)
+            .gradient(self.gradient.clone())
+            .easing(self.easing)
+            .rainbow(self.rainbow)
+            .hue_cycle(self.hue_cycle.map(Duration::from_secs_f64))
+            .color_depth(self.color_depth.unwrap_or_else(ColorDepth::detect))
+            .dither(self.dither)
+            .mutate(self.mutate)
+            .density(self.density)
+            .background(match self.background {
+                BackgroundArg::Auto if self.headless => Background::default(),
+                BackgroundArg::Auto => probe::background(),
+                BackgroundArg::Dark => Background::Dark,
+                BackgroundArg::Light => Background::Light,
+            })
+            .speed(self.speed)
+            .speed_dist(self.speed_dist)
+            .gravity(self.gravity)
+            .jitter(self.jitter)
+            .length(self.length)
+            .direction(self.direction)
+            .seed(self.seed)
     }
-    pub fn speed_range(&self) -> std::ops::Range<u64> {
-        let (max, min) = self.speed();
-        max..min
+}
+
+/// `--chars` help built from `Characters` itself so the two can't disagree.
+fn help_chars() -> String {
+    let groups: String = Characters::value_variants()
+        .iter()
+        .filter_map(ValueEnum::to_possible_value)
+        .map(|value| {
+            let help = value
+                .get_help()
+                .map(ToString::to_string)
+                .unwrap_or_default();
+            format!("    {:<14} - {}\n", value.get_name(), help)
+        })
+        .collect();
+    format!(
+        "Set what kind of characters are printed as rain.\nOPTIONS:\n{groups}\
+         Mix groups by weight with jap:70,num:25,alphaup:5\n"
+    )
+}
+
+pub fn parse_seconds(value: &str) -> Result<f64, String> {
+    match value.trim().parse::<f64>() {
+        Ok(seconds) if seconds > 0.0 && Duration::try_from_secs_f64(seconds).is_ok() => Ok(seconds),
+        Ok(seconds) if seconds > 0.0 => Err(String::from("too many seconds")),
+        Ok(_) => Err(String::from("must be more than 0 seconds")),
+        Err(e) => Err(format!("'{value}' is not a number of seconds: {e}")),
     }
 }
 
-pub fn into_color(value: &str) -> (u8, u8, u8) {
-    match value {
-        c if StrTuple::<(u8, u8, u8)>::into_tuple(c).is_ok() => match c.into_tuple() {
-            Ok((r, g, b)) => (r, g, b),
-            _ => (255, 255, 255),
-        },
-        "red" => (255, 0, 0),
-        "blue" => (0, 0, 255),
-        "green" => (0, 255, 0),
-        _ => (255, 255, 255),
+pub fn parse_rate(value: &str) -> Result<f64, String> {
+    match value.trim().parse::<f64>() {
+        Ok(rate) if (0.0..=1.0).contains(&rate) => Ok(rate),
+        Ok(_) => Err(String::from("must be from 0 to 1")),
+        Err(e) => Err(format!("'{value}' is not a number: {e}")),
     }
 }
 
-impl StrTuple<(u64, u64)> for &str {
-    type Error = std::num::ParseIntError;
-    fn into_tuple(self) -> Result<(u64, u64), Self::Error> {
-        let mut nums = Vec::new();
-        for num in self.split(',') {
-            nums.push(num.parse::<u64>()?);
-        }
-        let a = nums[0];
-        let b = nums[1];
-        Ok((a, b))
+pub fn parse_gravity(value: &str) -> Result<f64, String> {
+    match value.trim().parse::<f64>() {
+        Ok(gravity) if gravity >= 0.0 && gravity.is_finite() => Ok(gravity),
+        Ok(_) => Err(String::from("must be 0 or more")),
+        Err(e) => Err(format!("'{value}' is not a number: {e}")),
     }
 }
 
-impl StrTuple<(u8, u8, u8)> for &str {
-    type Error = std::num::ParseIntError;
-    fn into_tuple(self) -> Result<(u8, u8, u8), Self::Error> {
-        let mut nums = Vec::new();
-        for num in self.split(',') {
-            nums.push(num.parse::<u8>()?);
-        }
-        let a = nums[0];
-        let b = nums[1];
-        let c = nums[2];
-        Ok((a, b, c))
+pub fn parse_density(value: &str) -> Result<f64, String> {
+    match value.trim().parse::<f64>() {
+        Ok(density) if density > 0.0 && density <= 1.0 => Ok(density),
+        Ok(_) => Err(String::from("must be more than 0 and at most 1")),
+        Err(e) => Err(format!("'{value}' is not a number: {e}")),
     }
 }
 
-trait StrTuple<T> {
-    type Error;
-    fn into_tuple(self) -> Result<T, Self::Error>;
+fn parse_size(value: &str) -> Result<(u16, u16), String> {
+    let (w, h) = value
+        .split_once(['x', 'X'])
+        .ok_or_else(|| format!("expected WIDTHxHEIGHT like 120x40, got '{value}'"))?;
+    let w = w
+        .trim()
+        .parse::<u16>()
+        .map_err(|e| format!("bad width '{w}': {e}"))?;
+    let h = h
+        .trim()
+        .parse::<u16>()
+        .map_err(|e| format!("bad height '{h}': {e}"))?;
+    if w == 0 || h == 0 {
+        return Err(String::from("width and height must be at least 1"));
+    }
+    Ok((w, h))
 }
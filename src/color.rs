@@ -0,0 +1,467 @@
+use crate::gradient::Oklab;
+use crate::{style, Background};
+
+/// A 24 bit color parsed from any of the forms accepted on the command line.
+///
+/// ```text
+/// green  darkslategray   CSS/X11 color names
+/// #00ff41  #0f4          hex
+/// 0,255,65  rgb(0,255,65)
+/// hsl(120,100%,50%)
+/// ansi:46                xterm 256 color palette
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Color {
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+
+    /// Looks up a CSS/X11 color name, ignoring case, spaces, `-` and `_`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = normalize(name);
+        NAMES
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|&(_, rgb)| Self::from_hex(rgb))
+    }
+
+    /// Converts an index of the xterm 256 color palette.
+    pub fn from_ansi(index: u8) -> Self {
+        match index {
+            0..=15 => Self::from_hex(SYSTEM[index as usize]),
+            16..=231 => {
+                let i = index - 16;
+                let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+                Self::new(level(i / 36), level(i / 6 % 6), level(i % 6))
+            }
+            232..=255 => {
+                let v = 8 + (index - 232) * 10;
+                Self::new(v, v, v)
+            }
+        }
+    }
+
+    /// Hue in degrees, saturation and lightness from `0.0` to `1.0`.
+    pub fn from_hsl(hue: f32, saturation: f32, lightness: f32) -> Self {
+        let c = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        let h = hue.rem_euclid(360.0) / 60.0;
+        let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+        let (r, g, b) = match h as u32 {
+            0 => (c, x, 0.0),
+            1 => (x, c, 0.0),
+            2 => (0.0, c, x),
+            3 => (0.0, x, c),
+            4 => (x, 0.0, c),
+            _ => (c, 0.0, x),
+        };
+        let m = lightness - c / 2.0;
+        let channel = |v: f32| ((v + m) * 255.0).round().clamp(0.0, 255.0) as u8;
+        Self::new(channel(r), channel(g), channel(b))
+    }
+
+    /// Turns the hue by `degrees`, grays and white stay as they are.
+    pub fn rotate_hue(self, degrees: f32) -> Self {
+        Oklab::from(self).rotate(degrees).into()
+    }
+
+    /// Flips lightness so colors made for a dark background read on a light one.
+    pub fn invert_lightness(self) -> Self {
+        Oklab::from(self).invert().into()
+    }
+
+    /// `Light` if this is closer to white than to black.
+    pub fn background(self) -> Background {
+        match Oklab::from(self).lightness() > 0.6 {
+            true => Background::Light,
+            false => Background::Dark,
+        }
+    }
+
+    const fn from_hex(rgb: u32) -> Self {
+        Self::new((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)
+    }
+}
+
+impl From<(u8, u8, u8)> for Color {
+    fn from((r, g, b): (u8, u8, u8)) -> Self {
+        Self::new(r, g, b)
+    }
+}
+
+impl From<Color> for (u8, u8, u8) {
+    fn from(color: Color) -> Self {
+        (color.r, color.g, color.b)
+    }
+}
+
+impl From<Color> for style::Color {
+    fn from(Color { r, g, b }: Color) -> Self {
+        style::Color::Rgb { r, g, b }
+    }
+}
+
+impl std::fmt::Display for Color {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
+/// Why a color string could not be understood.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseColorError {
+    input: String,
+    reason: String,
+}
+
+impl ParseColorError {
+    fn new(input: &str, reason: impl Into<String>) -> Self {
+        Self {
+            input: input.into(),
+            reason: reason.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for ParseColorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "'{}' is not a color: {}", self.input, self.reason)
+    }
+}
+
+impl std::error::Error for ParseColorError {}
+
+impl std::str::FromStr for Color {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = s.trim();
+        let lower = value.to_ascii_lowercase();
+        if let Some(hex) = lower.strip_prefix('#') {
+            return parse_hex(hex)
+                .ok_or_else(|| ParseColorError::new(s, "hex colors look like #00ff41 or #0f4"));
+        }
+        if let Some(index) = lower.strip_prefix("ansi:") {
+            return index
+                .trim()
+                .parse::<u8>()
+                .map(Self::from_ansi)
+                .map_err(|_| ParseColorError::new(s, "ansi colors go from ansi:0 to ansi:255"));
+        }
+        if let Some(args) = function_args(&lower, "hsl") {
+            return parse_hsl(args)
+                .ok_or_else(|| ParseColorError::new(s, "hsl colors look like hsl(120,100%,50%)"));
+        }
+        let rgb = function_args(&lower, "rgb").unwrap_or(&lower);
+        if rgb.contains(',') {
+            return parse_rgb(rgb).ok_or_else(|| {
+                ParseColorError::new(
+                    s,
+                    "rgb colors are three numbers from 0 to 255 like 0,255,65",
+                )
+            });
+        }
+        Self::from_name(value).ok_or_else(|| {
+            let suggestions = suggest(value);
+            let reason = if suggestions.is_empty() {
+                String::from("expected a color name, #rrggbb, r,g,b, hsl(h,s%,l%) or ansi:N")
+            } else {
+                format!("did you mean {}?", suggestions.join(", "))
+            };
+            ParseColorError::new(s, reason)
+        })
+    }
+}
+
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '_'))
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+fn function_args<'a>(value: &'a str, name: &str) -> Option<&'a str> {
+    value
+        .strip_prefix(name)?
+        .trim_start()
+        .strip_prefix('(')?
+        .strip_suffix(')')
+}
+
+fn parse_hex(hex: &str) -> Option<Color> {
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let digit = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).ok();
+    match hex.len() {
+        3 => Some(Color::new(digit(0)? * 17, digit(1)? * 17, digit(2)? * 17)),
+        6 => u32::from_str_radix(hex, 16).ok().map(Color::from_hex),
+        _ => None,
+    }
+}
+
+fn parse_rgb(value: &str) -> Option<Color> {
+    let channels = value
+        .split(',')
+        .map(|n| n.trim().parse::<u8>().ok())
+        .collect::<Option<Vec<u8>>>()?;
+    match channels[..] {
+        [r, g, b] => Some(Color::new(r, g, b)),
+        _ => None,
+    }
+}
+
+fn parse_hsl(value: &str) -> Option<Color> {
+    let parts: Vec<&str> = value.split(',').map(str::trim).collect();
+    let [h, s, l] = parts[..] else {
+        return None;
+    };
+    let percent = |v: &str| {
+        v.strip_suffix('%')
+            .unwrap_or(v)
+            .trim()
+            .parse::<f32>()
+            .ok()
+            .filter(|p| (0.0..=100.0).contains(p))
+            .map(|p| p / 100.0)
+    };
+    let h = h.strip_suffix("deg").unwrap_or(h).parse::<f32>().ok()?;
+    Some(Color::from_hsl(h, percent(s)?, percent(l)?))
+}
+
+/// Up to three color names that are a small typo away from `value`.
+fn suggest(value: &str) -> Vec<&'static str> {
+    let value = normalize(value);
+    let mut close: Vec<(usize, &str)> = NAMES
+        .iter()
+        .map(|(name, _)| (distance(&value, name), *name))
+        .filter(|(d, name)| *d <= 2 || (value.len() > 2 && name.starts_with(&value)))
+        .collect();
+    close.sort();
+    close.into_iter().take(3).map(|(_, name)| name).collect()
+}
+
+/// Levenshtein distance between two ASCII strings.
+fn distance(a: &str, b: &str) -> usize {
+    let b = b.as_bytes();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.bytes().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            let next = (row[j + 1] + 1).min(row[j] + 1).min(prev + cost);
+            prev = row[j + 1];
+            row[j + 1] = next;
+        }
+    }
+    row[b.len()]
+}
+
+/// xterm's default colors for the first 16 palette entries.
+pub(crate) const SYSTEM: [u32; 16] = [
+    0x000000, 0xcd0000, 0x00cd00, 0xcdcd00, 0x0000ee, 0xcd00cd, 0x00cdcd, 0xe5e5e5, 0x7f7f7f,
+    0xff0000, 0x00ff00, 0xffff00, 0x5c5cff, 0xff00ff, 0x00ffff, 0xffffff,
+];
+
+/// CSS Color Module Level 4 named colors, which include the X11 set.
+///
+/// Where CSS and X11 disagree the X11 value is used for `green` since that is what
+/// `-C green` has always meant here, CSS green is still there as `webgreen`.
+const NAMES: &[(&str, u32)] = &[
+    ("aliceblue", 0xf0f8ff),
+    ("antiquewhite", 0xfaebd7),
+    ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2),
+    ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50),
+    ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520),
+    ("gray", 0x808080),
+    ("green", 0x00ff00),
+    ("greenyellow", 0xadff2f),
+    ("grey", 0x808080),
+    ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd),
+    ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0),
+    ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead),
+    ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500),
+    ("orangered", 0xff4500),
+    ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f),
+    ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c),
+    ("teal", 0x008080),
+    ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347),
+    ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee),
+    ("webgreen", 0x008000),
+    ("wheat", 0xf5deb3),
+    ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::Color;
+
+    #[test]
+    fn parses_every_form() {
+        let cases = [
+            ("#00ff41", Color::new(0, 255, 65)),
+            ("#0F4", Color::new(0, 255, 68)),
+            ("0,255,65", Color::new(0, 255, 65)),
+            ("rgb( 0, 255, 65 )", Color::new(0, 255, 65)),
+            ("hsl(120,100%,50%)", Color::new(0, 255, 0)),
+            ("ansi:46", Color::new(0, 255, 0)),
+            ("white", Color::new(255, 255, 255)),
+            (" Dark-Cyan ", Color::new(0, 139, 139)),
+        ];
+        for (input, color) in cases {
+            assert_eq!(input.parse::<Color>(), Ok(color), "{input}");
+        }
+    }
+
+    #[test]
+    fn explains_what_is_wrong() {
+        let cases = [
+            ("#12", "hex colors"),
+            ("#ggg", "hex colors"),
+            ("ansi:256", "ansi colors"),
+            ("1,2", "rgb colors"),
+            ("300,0,0", "rgb colors"),
+            ("hsl(1,2)", "hsl colors"),
+            ("hsl(0,150%,50%)", "hsl colors"),
+            ("grene", "did you mean green"),
+            ("", "expected a color name"),
+        ];
+        for (input, reason) in cases {
+            let error = input.parse::<Color>().expect_err(input).to_string();
+            assert!(error.contains(reason), "{input}: {error}");
+        }
+    }
+}
@@ -0,0 +1,387 @@
+use crate::cli::{self, BackgroundArg, Cli};
+use crate::theme::Theme;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, ValueEnum};
+use rusty_rain::{
+    Color, ColorDepth, CustomChars, Direction, Easing, Gradient, LengthRange, Mix, SpeedDist,
+    SpeedRange,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Settings that can be read from the config file, anything left out keeps its default.
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct Settings {
+    pub shade: Option<bool>,
+    pub chars: Option<String>,
+    pub per_column: Option<bool>,
+    pub sequence: Option<bool>,
+    pub chars_custom: Option<Vec<String>>,
+    pub chars_file: Option<PathBuf>,
+    pub color: Option<String>,
+    pub head: Option<String>,
+    pub direction: Option<String>,
+    pub speed: Option<String>,
+    pub speed_dist: Option<String>,
+    pub gravity: Option<f64>,
+    pub jitter: Option<f64>,
+    pub length: Option<String>,
+    pub gradient: Option<String>,
+    pub easing: Option<String>,
+    pub rainbow: Option<bool>,
+    pub hue_cycle: Option<f64>,
+    pub color_depth: Option<String>,
+    pub dither: Option<bool>,
+    pub background: Option<String>,
+    pub mutate: Option<f64>,
+    pub density: Option<f64>,
+    pub theme: Option<String>,
+}
+
+/// Layout of `config.toml`, top level settings plus any number of `[profile.<name>]` tables.
+#[derive(Debug, Default, Deserialize)]
+pub struct Config {
+    #[serde(flatten)]
+    pub settings: Settings,
+    #[serde(default)]
+    pub profile: BTreeMap<String, Settings>,
+}
+
+impl Settings {
+    /// Fills in anything missing from `self` with `other`.
+    pub fn or(self, other: Self) -> Self {
+        Self {
+            shade: self.shade.or(other.shade),
+            chars: self.chars.or(other.chars),
+            per_column: self.per_column.or(other.per_column),
+            sequence: self.sequence.or(other.sequence),
+            chars_custom: self.chars_custom.or(other.chars_custom),
+            chars_file: self.chars_file.or(other.chars_file),
+            color: self.color.or(other.color),
+            head: self.head.or(other.head),
+            direction: self.direction.or(other.direction),
+            speed: self.speed.or(other.speed),
+            speed_dist: self.speed_dist.or(other.speed_dist),
+            gravity: self.gravity.or(other.gravity),
+            jitter: self.jitter.or(other.jitter),
+            length: self.length.or(other.length),
+            gradient: self.gradient.or(other.gradient),
+            easing: self.easing.or(other.easing),
+            rainbow: self.rainbow.or(other.rainbow),
+            hue_cycle: self.hue_cycle.or(other.hue_cycle),
+            color_depth: self.color_depth.or(other.color_depth),
+            dither: self.dither.or(other.dither),
+            background: self.background.or(other.background),
+            mutate: self.mutate.or(other.mutate),
+            density: self.density.or(other.density),
+            theme: self.theme.or(other.theme),
+        }
+    }
+
+    /// Writes these settings into `cli` wherever neither a flag nor an environment
+    /// variable already set a value.
+    pub fn apply_to(&self, cli: &mut Cli, matches: &ArgMatches) -> Result<(), String> {
+        let unset = |id: &str| {
+            matches!(
+                matches.value_source(id),
+                None | Some(ValueSource::DefaultValue)
+            )
+        };
+        if let Some(shade) = self.shade.filter(|_| unset("shade")) {
+            cli.shade = shade;
+        }
+        if let Some(chars) = self.chars.as_deref().filter(|_| unset("chars")) {
+            cli.chars = chars.parse::<Mix>().map_err(|e| format!("chars: {e}"))?;
+        }
+        if let Some(per_column) = self.per_column.filter(|_| unset("per_column")) {
+            cli.per_column = per_column;
+        }
+        if let Some(sequence) = self.sequence.filter(|_| unset("sequence")) {
+            cli.sequence = sequence;
+        }
+        // Custom characters replace --chars, so they only come from the file when
+        // --chars wasn't given either.
+        let custom_unset = |id: &str| unset(id) && unset("chars");
+        if let Some(specs) = self
+            .chars_custom
+            .as_ref()
+            .filter(|_| custom_unset("chars_custom"))
+        {
+            cli.chars_custom = specs
+                .iter()
+                .map(|spec| spec.parse::<CustomChars>())
+                .collect::<Result<_, _>>()
+                .map_err(|e| format!("chars_custom: {e}"))?;
+        }
+        if let Some(path) = self
+            .chars_file
+            .as_ref()
+            .filter(|_| custom_unset("chars_file"))
+        {
+            cli.chars_file = Some(path.clone());
+        }
+        if let Some(color) = self.color.as_deref().filter(|_| unset("color")) {
+            cli.color = color.parse::<Color>().map_err(|e| format!("color: {e}"))?;
+        }
+        if let Some(head) = self.head.as_deref().filter(|_| unset("head")) {
+            cli.head = head.parse::<Color>().map_err(|e| format!("head: {e}"))?;
+        }
+        if let Some(direction) = self.direction.as_deref().filter(|_| unset("direction")) {
+            cli.direction =
+                Direction::from_str(direction, true).map_err(|e| format!("direction: {e}"))?;
+        }
+        if let Some(speed) = self.speed.as_deref().filter(|_| unset("speed")) {
+            cli.speed = speed
+                .parse::<SpeedRange>()
+                .map_err(|e| format!("speed: {e}"))?;
+        }
+        if let Some(dist) = self.speed_dist.as_deref().filter(|_| unset("speed_dist")) {
+            cli.speed_dist =
+                SpeedDist::from_str(dist, true).map_err(|e| format!("speed_dist: {e}"))?;
+        }
+        if let Some(gravity) = self.gravity.filter(|_| unset("gravity")) {
+            cli.gravity =
+                cli::parse_gravity(&gravity.to_string()).map_err(|e| format!("gravity: {e}"))?;
+        }
+        if let Some(jitter) = self.jitter.filter(|_| unset("jitter")) {
+            cli.jitter =
+                cli::parse_rate(&jitter.to_string()).map_err(|e| format!("jitter: {e}"))?;
+        }
+        if let Some(length) = self.length.as_deref().filter(|_| unset("length")) {
+            cli.length = Some(
+                length
+                    .parse::<LengthRange>()
+                    .map_err(|e| format!("length: {e}"))?,
+            );
+        }
+        if let Some(gradient) = self.gradient.as_deref().filter(|_| unset("gradient")) {
+            cli.gradient = Some(
+                gradient
+                    .parse::<Gradient>()
+                    .map_err(|e| format!("gradient: {e}"))?,
+            );
+        }
+        if let Some(easing) = self.easing.as_deref().filter(|_| unset("easing")) {
+            cli.easing = Easing::from_str(easing, true).map_err(|e| format!("easing: {e}"))?;
+        }
+        if let Some(rainbow) = self.rainbow.filter(|_| unset("rainbow")) {
+            cli.rainbow = rainbow;
+        }
+        if let Some(seconds) = self.hue_cycle.filter(|_| unset("hue_cycle")) {
+            cli.hue_cycle = Some(
+                cli::parse_seconds(&seconds.to_string()).map_err(|e| format!("hue_cycle: {e}"))?,
+            );
+        }
+        if let Some(depth) = self.color_depth.as_deref().filter(|_| unset("color_depth")) {
+            cli.color_depth =
+                Some(ColorDepth::from_str(depth, true).map_err(|e| format!("color_depth: {e}"))?);
+        }
+        if let Some(dither) = self.dither.filter(|_| unset("dither")) {
+            cli.dither = dither;
+        }
+        if let Some(background) = self.background.as_deref().filter(|_| unset("background")) {
+            cli.background = BackgroundArg::from_str(background, true)
+                .map_err(|e| format!("background: {e}"))?;
+        }
+        if let Some(rate) = self.mutate.filter(|_| unset("mutate")) {
+            cli.mutate = cli::parse_rate(&rate.to_string()).map_err(|e| format!("mutate: {e}"))?;
+        }
+        if let Some(density) = self.density.filter(|_| unset("density")) {
+            cli.density = Some(
+                cli::parse_density(&density.to_string()).map_err(|e| format!("density: {e}"))?,
+            );
+        }
+        Ok(())
+    }
+}
+
+impl From<&Cli> for Settings {
+    fn from(cli: &Cli) -> Self {
+        Self {
+            shade: Some(cli.shade),
+            chars: Some(cli.chars.to_string()),
+            per_column: Some(cli.per_column),
+            sequence: Some(cli.sequence),
+            chars_custom: Some(&cli.chars_custom)
+                .filter(|specs| !specs.is_empty())
+                .map(|specs| specs.iter().map(ToString::to_string).collect()),
+            chars_file: cli.chars_file.clone(),
+            color: Some(cli.color.to_string()),
+            head: Some(cli.head.to_string()),
+            direction: cli
+                .direction
+                .to_possible_value()
+                .map(|v| v.get_name().into()),
+            speed: Some(cli.speed.to_string()),
+            speed_dist: Some(cli.speed_dist.to_string()),
+            gravity: Some(cli.gravity),
+            jitter: Some(cli.jitter),
+            length: cli.length.map(|length| length.to_string()),
+            gradient: cli.gradient.as_ref().map(ToString::to_string),
+            easing: Some(cli.easing.to_string()),
+            rainbow: Some(cli.rainbow),
+            hue_cycle: cli.hue_cycle,
+            color_depth: cli.color_depth.map(|depth| depth.to_string()),
+            dither: Some(cli.dither),
+            background: Some(cli.background.to_string()),
+            mutate: Some(cli.mutate),
+            density: cli.density,
+            theme: None,
+        }
+    }
+}
+
+/// `$XDG_CONFIG_HOME/rusty-rain/config.toml`, falling back to `~/.config`.
+pub fn default_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(base.join("rusty-rain").join("config.toml"))
+}
+
+pub fn read(path: &Path) -> Result<Config, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    toml::from_str(&text).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+/// Loads the config file and chosen profile into `cli`.
+///
+/// Flags beat environment variables, which beat the profile, which beats the
+/// top level of the file. Themes sit underneath whichever of these named them.
+pub fn apply(cli: &mut Cli, matches: &ArgMatches) -> Result<(), String> {
+    let config = match (&cli.config, default_path()) {
+        (Some(path), _) => read(path)?,
+        (None, Some(path)) if path.exists() => read(&path)?,
+        _ => Config::default(),
+    };
+    apply_config(cli, matches, config)
+}
+
+fn apply_config(cli: &mut Cli, matches: &ArgMatches, config: Config) -> Result<(), String> {
+    let mut settings = config.settings;
+    if let Some(name) = &cli.profile {
+        let profile = config
+            .profile
+            .get(name)
+            .ok_or_else(|| format!("no profile named '{name}' in the config file"))?;
+        settings = profile.clone().or(settings);
+    }
+    // A theme picked on the command line replaces the file's settings, one named
+    // in the file only fills in what the file leaves out.
+    settings = match (cli.theme, settings.theme.as_deref()) {
+        // Custom characters would win over the theme's, so they go along with the rest.
+        (Some(theme), _) => theme.settings().or(Settings {
+            chars_custom: None,
+            chars_file: None,
+            ..settings
+        }),
+        (None, Some(name)) => {
+            let theme = Theme::from_str(name, true).map_err(|e| format!("theme: {e}"))?;
+            settings.or(theme.settings())
+        }
+        (None, None) => settings,
+    };
+    settings.apply_to(cli, matches)
+}
+
+/// The settings in effect as `config.toml` text.
+pub fn effective(cli: &Cli) -> String {
+    toml::to_string(&Settings::from(cli)).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_config, Config};
+    use crate::cli::Cli;
+    use clap::{CommandFactory, FromArgMatches};
+    use rusty_rain::{Color, Direction, SpeedRange};
+
+    const FILE: &str = r#"
+        color = "red"
+        head = "red"
+        speed = "10..20"
+        chars_custom = ["01ABCDEF"]
+
+        [profile.calm]
+        color = "blue"
+        speed = "300..400"
+    "#;
+
+    fn load(args: &[&str], file: &str) -> Cli {
+        let args = std::iter::once("rusty-rain").chain(args.iter().copied());
+        let matches = Cli::command()
+            .try_get_matches_from(args)
+            .expect("valid arguments");
+        let mut cli = Cli::from_arg_matches(&matches).expect("valid arguments");
+        let config: Config = toml::from_str(file).expect("valid config");
+        apply_config(&mut cli, &matches, config).expect("valid settings");
+        cli
+    }
+
+    fn color(name: &str) -> Color {
+        name.parse().expect("valid color")
+    }
+
+    #[test]
+    fn flags_beat_the_profile_and_the_file() {
+        let cli = load(&["--profile", "calm", "-C", "yellow", "-S", "5..6"], FILE);
+        assert_eq!(cli.color, color("yellow"));
+        assert_eq!(cli.speed, SpeedRange::new(5, 6).unwrap());
+        // Anything the flags leave out still comes from the file.
+        assert_eq!(cli.head, color("red"));
+    }
+
+    #[test]
+    fn environment_beats_the_profile_and_the_file() {
+        let file = r#"
+            direction = "left"
+
+            [profile.calm]
+            direction = "right"
+        "#;
+        // Nothing else in these tests reads the direction, so setting it can't race them.
+        std::env::set_var("RUSTY_RAIN_DIRECTION", "up");
+        let cli = load(&["--profile", "calm"], file);
+        let flagged = load(&["--profile", "calm", "-d", "down"], file);
+        std::env::remove_var("RUSTY_RAIN_DIRECTION");
+        assert_eq!(cli.direction, Direction::Up);
+        assert_eq!(flagged.direction, Direction::Down);
+        assert_eq!(
+            load(&["--profile", "calm"], file).direction,
+            Direction::Right
+        );
+        assert_eq!(load(&[], file).direction, Direction::Left);
+    }
+
+    #[test]
+    fn profile_beats_the_file() {
+        let cli = load(&["--profile", "calm"], FILE);
+        assert_eq!(cli.color, color("blue"));
+        assert_eq!(cli.speed, SpeedRange::new(300, 400).unwrap());
+        assert_eq!(cli.head, color("red"));
+    }
+
+    #[test]
+    fn file_beats_a_theme_it_names() {
+        let cli = load(&[], &format!("theme = \"ice\"\n{FILE}"));
+        assert_eq!(cli.color, color("red"));
+        assert_eq!(cli.speed, SpeedRange::new(10, 20).unwrap());
+        assert_eq!(cli.chars_custom.len(), 1);
+        // Only what the file leaves out comes from the theme.
+        assert!(cli.shade);
+    }
+
+    #[test]
+    fn theme_flag_replaces_the_file() {
+        let cli = load(&["--theme", "ice"], FILE);
+        assert_eq!(cli.color, color("120,200,255"));
+        assert_eq!(cli.speed, SpeedRange::new(50, 250).unwrap());
+        assert_eq!(cli.chars.to_string(), "alphaup");
+        assert!(cli.chars_custom.is_empty());
+        assert_eq!(cli.chars_file, None);
+        let cli = load(&["--theme", "ice", "-C", "yellow"], FILE);
+        assert_eq!(cli.color, color("yellow"));
+    }
+}
@@ -0,0 +1,158 @@
+use crate::color::SYSTEM;
+use crate::gradient::Oklab;
+use crate::{style, Background, Color};
+use clap::ValueEnum;
+
+/// How many colors the terminal can show.
+#[derive(Debug, ValueEnum, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorDepth {
+    /// 24 bit color.
+    #[default]
+    #[value(alias = "24bit")]
+    Truecolor,
+    /// The xterm 256 color palette.
+    #[value(name = "256")]
+    Ansi256,
+    /// The 16 standard colors, works on the Linux console.
+    #[value(name = "16")]
+    Ansi16,
+    /// No color, bold and dim only.
+    Mono,
+}
+
+impl std::fmt::Display for ColorDepth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            Self::Truecolor => "truecolor",
+            Self::Ansi256 => "256",
+            Self::Ansi16 => "16",
+            Self::Mono => "mono",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+/// Levels of each channel in the 6x6x6 cube of the 256 color palette.
+const CUBE: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// Ordered dither thresholds, picked by how far a cell is behind the head.
+const BAYER: [f32; 4] = [0.125, 0.625, 0.375, 0.875];
+
+const NAMED: [style::Color; 16] = [
+    style::Color::Black,
+    style::Color::DarkRed,
+    style::Color::DarkGreen,
+    style::Color::DarkYellow,
+    style::Color::DarkBlue,
+    style::Color::DarkMagenta,
+    style::Color::DarkCyan,
+    style::Color::Grey,
+    style::Color::DarkGrey,
+    style::Color::Red,
+    style::Color::Green,
+    style::Color::Yellow,
+    style::Color::Blue,
+    style::Color::Magenta,
+    style::Color::Cyan,
+    style::Color::White,
+];
+
+impl ColorDepth {
+    /// Guesses from `NO_COLOR`, `COLORTERM` and `TERM`.
+    pub fn detect() -> Self {
+        let var = |name: &str| std::env::var(name).unwrap_or_default().to_ascii_lowercase();
+        if !var("NO_COLOR").is_empty() {
+            return Self::Mono;
+        }
+        let (colorterm, term) = (var("COLORTERM"), var("TERM"));
+        if colorterm == "truecolor" || colorterm == "24bit" || term.contains("direct") {
+            Self::Truecolor
+        } else if term.contains("256color") {
+            Self::Ansi256
+        } else if term == "dumb" {
+            Self::Mono
+        } else if term.is_empty() && cfg!(windows) {
+            // Windows Terminal and conhost both take 24 bit color.
+            Self::Truecolor
+        } else {
+            Self::Ansi16
+        }
+    }
+
+    /// Styles a color that sits `index` cells behind the head of its drop.
+    ///
+    /// With `dither` neighbouring cells round in different directions so a fade
+    /// through a small palette doesn't turn into a few solid bands.
+    pub fn style(
+        &self,
+        color: style::Color,
+        index: usize,
+        dither: bool,
+        background: Background,
+    ) -> style::ContentStyle {
+        let mut content = style::ContentStyle::new();
+        let style::Color::Rgb { r, g, b } = color else {
+            content.foreground_color = Some(color);
+            return content;
+        };
+        let offset = |spread: f32| match dither {
+            true => (BAYER[index % BAYER.len()] - 0.5) * spread,
+            false => 0.0,
+        };
+        let nudge = |spread: f32| {
+            let o = offset(spread);
+            let c = |v: u8| (v as f32 + o).round().clamp(0.0, 255.0) as u8;
+            Color::new(c(r), c(g), c(b))
+        };
+        match self {
+            Self::Truecolor => content.foreground_color = Some(color),
+            Self::Ansi256 => content.foreground_color = Some(ansi256(nudge(40.0))),
+            Self::Ansi16 => content.foreground_color = Some(ansi16(nudge(96.0))),
+            Self::Mono => {
+                let lightness = Oklab::from(Color::new(r, g, b)).lightness();
+                let contrast = match background {
+                    Background::Dark => lightness,
+                    Background::Light => 1.0 - lightness,
+                };
+                if index == 0 {
+                    content.attributes.set(style::Attribute::Bold);
+                } else if contrast + offset(0.2) < 0.45 {
+                    content.attributes.set(style::Attribute::Dim);
+                }
+            }
+        }
+        content
+    }
+}
+
+fn ansi256(color: Color) -> style::Color {
+    let level = |v: u8| {
+        CUBE.iter()
+            .enumerate()
+            .min_by_key(|(_, l)| (**l as i16 - v as i16).abs())
+            .map_or(0, |(i, _)| i as u8)
+    };
+    let (r, g, b) = (level(color.r), level(color.g), level(color.b));
+    let cube = Color::new(CUBE[r as usize], CUBE[g as usize], CUBE[b as usize]);
+    let average = (color.r as u16 + color.g as u16 + color.b as u16) / 3;
+    let step = (average.saturating_sub(3) / 10).min(23) as u8;
+    let gray = Color::from_ansi(232 + step);
+    if distance(color, gray) < distance(color, cube) {
+        style::Color::AnsiValue(232 + step)
+    } else {
+        style::Color::AnsiValue(16 + 36 * r + 6 * g + b)
+    }
+}
+
+fn ansi16(color: Color) -> style::Color {
+    SYSTEM
+        .iter()
+        .map(|&rgb| Color::new((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8))
+        .zip(NAMED)
+        .min_by(|(a, _), (b, _)| distance(color, *a).total_cmp(&distance(color, *b)))
+        .map_or(style::Color::Reset, |(_, named)| named)
+}
+
+fn distance(a: Color, b: Color) -> f32 {
+    Oklab::from(a).distance(Oklab::from(b))
+}
@@ -18,3 +18,25 @@ impl std::fmt::Display for Direction {
         }
     }
 }
+
+impl Direction {
+    /// Swaps the terminal size into (columns, rows) as seen by the falling rain,
+    /// where every glyph is `spacing` cells wide.
+    pub fn rain_size(self, width: u16, height: u16, spacing: u16) -> (u16, u16) {
+        let spacing = spacing.max(1);
+        match self {
+            Self::Left | Self::Right => (height, width / spacing),
+            Self::Up | Self::Down => (width / spacing, height),
+        }
+    }
+
+    /// Maps a rain column and row onto the screen cell where its glyph starts.
+    pub fn to_screen(self, column: u16, row: u16, height: u16, spacing: u16) -> (u16, u16) {
+        match self {
+            Self::Down => (column * spacing, row),
+            Self::Up => (column * spacing, height - row),
+            Self::Right => (row * spacing, column),
+            Self::Left => ((height - row) * spacing, column),
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use crate::gen::{self, ColorFn};
+use crate::term::{draw, Renderer};
+use crate::update::{mutate, reset, update};
+use crate::{style, GlyphTable, Rain, UserSettings};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::time::Instant;
+
+/// A single glyph currently visible on screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell<'a> {
+    pub x: u16,
+    pub y: u16,
+    pub glyph: &'a str,
+    pub color: style::Color,
+}
+
+/// Drives the rain simulation independent of any terminal.
+pub struct RainEngine {
+    settings: UserSettings,
+    create_color: ColorFn,
+    rain: Rain,
+    rng: StdRng,
+    now: Instant,
+    start: Instant,
+}
+
+impl std::fmt::Debug for RainEngine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RainEngine")
+            .field("settings", &self.settings)
+            .field("rain", &self.rain)
+            .field("now", &self.now)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RainEngine {
+    /// Creates the rain for a screen of `width` x `height` cells.
+    pub fn new(settings: UserSettings, width: u16, height: u16) -> Self {
+        let create_color = gen::color_function(&settings);
+        let glyphs = GlyphTable::with_widths(&settings.group, &settings.glyph_widths)
+            .sequence(settings.sequence);
+        let mut rng = match settings.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        let now = Instant::now();
+        let (w, h) = settings.direction.rain_size(width, height, glyphs.width());
+        let rain = Rain::new(&create_color, glyphs, w, h, &settings, &mut rng, now);
+        Self {
+            settings,
+            create_color,
+            rain,
+            rng,
+            now,
+            start: now,
+        }
+    }
+
+    /// Throws away the current rain and starts over on a screen of the new size.
+    pub fn resize(&mut self, width: u16, height: u16) {
+        let spacing = self.rain.glyphs.width();
+        let (w, h) = self.settings.direction.rain_size(width, height, spacing);
+        // The glyph table only depends on the settings, so it carries over.
+        self.rain = Rain::new(
+            &self.create_color,
+            self.rain.glyphs.clone(),
+            w,
+            h,
+            &self.settings,
+            &mut self.rng,
+            self.now,
+        );
+    }
+
+    /// Moves every drop that is due at `now` and restarts the ones that left the screen.
+    pub fn step(&mut self, now: Instant) {
+        self.now = now;
+        if let Some(period) = self.settings.hue_cycle.filter(|p| !p.is_zero()) {
+            let turns = now.duration_since(self.start).as_secs_f32() / period.as_secs_f32();
+            self.rain.hue_shift = turns.fract() * 360.0;
+            self.rain.repaint = true;
+        }
+        update(&mut self.rain, &self.settings, &mut self.rng, now);
+        reset(
+            &self.create_color,
+            &mut self.rain,
+            &self.settings,
+            &mut self.rng,
+            now,
+        );
+        mutate(&mut self.rain, &self.settings, &mut self.rng);
+    }
+
+    /// The time of the last `step`, or of creation if there was none.
+    ///
+    /// Stepping with `now() + tick` instead of the wall clock makes a seeded run repeatable.
+    pub fn now(&self) -> Instant {
+        self.now
+    }
+
+    pub fn rain(&self) -> &Rain {
+        &self.rain
+    }
+
+    pub fn settings(&self) -> &UserSettings {
+        &self.settings
+    }
+
+    /// Every visible glyph in screen coordinates.
+    pub fn cells(&self) -> impl Iterator<Item = Cell<'_>> + '_ {
+        let rain = &self.rain;
+        let spacing = rain.glyphs.width();
+        let direction = self.settings.direction;
+        (0..rain.locations.len()).flat_map(move |drop| {
+            let column = rain.column(drop) as u16;
+            rain.visible(drop).map(move |(row, glyph, color)| {
+                let (x, y) = direction.to_screen(column, row as u16, rain.height, spacing);
+                Cell { x, y, glyph, color }
+            })
+        })
+    }
+
+    /// Draws the columns that moved during the last `step`.
+    pub fn render<R: Renderer>(&self, renderer: &mut R) -> std::io::Result<()> {
+        draw(renderer, &self.rain, &self.settings)
+    }
+}
@@ -0,0 +1,99 @@
+use crate::glyphs::glyph_width;
+use crate::style;
+use crate::term::Renderer;
+
+/// An in memory screen, handy for tests and for anything that is not a terminal.
+#[derive(Debug, Clone)]
+pub struct FrameBuffer {
+    width: u16,
+    height: u16,
+    cells: Vec<Option<(String, style::ContentStyle)>>,
+}
+
+impl FrameBuffer {
+    pub fn new(width: u16, height: u16) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![None; width as usize * height as usize],
+        }
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// The glyph and its style at `x`, `y`, `None` if blank or off screen.
+    pub fn get(&self, x: u16, y: u16) -> Option<(&str, style::ContentStyle)> {
+        self.index(x, y)
+            .and_then(|i| self.cells[i].as_ref())
+            .map(|(glyph, style)| (glyph.as_str(), *style))
+    }
+
+    fn index(&self, x: u16, y: u16) -> Option<usize> {
+        (x < self.width && y < self.height).then(|| y as usize * self.width as usize + x as usize)
+    }
+}
+
+impl Renderer for FrameBuffer {
+    fn draw_glyph(
+        &mut self,
+        x: u16,
+        y: u16,
+        glyph: &str,
+        style: style::ContentStyle,
+    ) -> std::io::Result<()> {
+        if let Some(i) = self.index(x, y) {
+            self.cells[i] = Some((glyph.into(), style));
+        }
+        // A wide glyph covers the cells after it, like it would in a terminal.
+        for dx in 1..glyph_width(glyph) {
+            if let Some(i) = self.index(x.saturating_add(dx), y) {
+                self.cells[i] = None;
+            }
+        }
+        Ok(())
+    }
+
+    fn erase(&mut self, x: u16, y: u16, width: u16) -> std::io::Result<()> {
+        for dx in 0..width {
+            if let Some(i) = self.index(x.saturating_add(dx), y) {
+                self.cells[i] = None;
+            }
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self) -> std::io::Result<()> {
+        self.cells.fill(None);
+        Ok(())
+    }
+}
+
+/// Prints the glyphs row by row, blanks as spaces.
+///
+/// Wide glyphs swallow the cells they cover so every line lines up in a terminal.
+impl std::fmt::Display for FrameBuffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in self.cells.chunks(self.width.max(1) as usize) {
+            let mut line = String::with_capacity(row.len());
+            let mut covered = 0;
+            for cell in row {
+                match cell {
+                    _ if covered > 0 => covered -= 1,
+                    Some((glyph, _)) => {
+                        line.push_str(glyph);
+                        covered = glyph_width(glyph).saturating_sub(1);
+                    }
+                    None => line.push(' '),
+                }
+            }
+            writeln!(f, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
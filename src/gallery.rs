@@ -0,0 +1,94 @@
+use crate::cli::Cli;
+use crate::FRAME_MS;
+use clap::ValueEnum;
+use crossterm::{cursor, event, execute, queue, style, terminal};
+use rusty_rain::{Characters, CrosstermRenderer, GlyphTable, Mix, RainEngine, Renderer};
+use std::io::stdout;
+use std::time::{Duration, Instant};
+
+/// Cells of sample glyphs shown for each group in `--list-chars`.
+const SAMPLE_CELLS: usize = 32;
+
+/// Largest area `--preview` rains in, kept small so it reads as a sample.
+const PREVIEW_SIZE: (u16, u16) = (40, 12);
+
+/// Frames `--preview` runs for when no key is pressed.
+const PREVIEW_FRAMES: u64 = 100;
+
+/// One line per character group with its width, glyph count and a few glyphs picked
+/// evenly from the whole group, for `--list-chars`.
+pub fn list() -> String {
+    let mut out = format!("{:<14} {:>5} {:>6}  sample\n", "name", "width", "glyphs");
+    for group in Characters::value_variants() {
+        let name = group
+            .to_possible_value()
+            .map(|value| value.get_name().to_string())
+            .unwrap_or_default();
+        // The same table the rain draws from, so widths and counts match what is shown.
+        let table = GlyphTable::new(&(*group).into());
+        let glyphs: Vec<&str> = table.glyphs().collect();
+        let width = table.width();
+        let count = SAMPLE_CELLS / width.max(1) as usize;
+        let step = glyphs.len().div_ceil(count).max(1);
+        let sample: String = glyphs.iter().step_by(step).take(count).copied().collect();
+        out.push_str(&format!(
+            "{:<14} {:>5} {:>6}  {}\n",
+            name,
+            width,
+            glyphs.len(),
+            sample
+        ));
+    }
+    out
+}
+
+/// Rains `group` with the rest of the settings in a small corner of the screen until
+/// a key is pressed or a few seconds pass.
+pub fn preview(cli: &Cli, group: &Mix) -> std::io::Result<()> {
+    let mut cli = cli.clone();
+    cli.chars = group.clone();
+    cli.custom = None;
+    let settings = cli.user_settings();
+    let (width, height) = terminal::size()?;
+    // One row below the rain is left for the label.
+    let width = width.min(PREVIEW_SIZE.0);
+    let height = height.saturating_sub(1).clamp(1, PREVIEW_SIZE.1);
+    let mut engine = RainEngine::new(settings, width, height);
+    let mut renderer = CrosstermRenderer::new(stdout());
+
+    terminal::enable_raw_mode()?;
+    execute!(
+        renderer.writer_mut(),
+        terminal::EnterAlternateScreen,
+        cursor::Hide
+    )?;
+    let result = (|| {
+        queue!(
+            renderer.writer_mut(),
+            cursor::MoveTo(0, height),
+            style::Print(format!("{group} - press any key to stop")),
+        )?;
+        for _ in 0..PREVIEW_FRAMES {
+            engine.render(&mut renderer)?;
+            renderer.flush()?;
+            if event::poll(Duration::from_millis(FRAME_MS))?
+                && matches!(event::read()?, event::Event::Key(_))
+            {
+                break;
+            }
+            let now = match cli.fixed_clock {
+                Some(ms) => engine.now() + Duration::from_millis(ms),
+                None => Instant::now(),
+            };
+            engine.step(now);
+        }
+        Ok(())
+    })();
+    execute!(
+        renderer.writer_mut(),
+        cursor::Show,
+        terminal::LeaveAlternateScreen
+    )?;
+    terminal::disable_raw_mode()?;
+    result
+}
@@ -1,84 +1,194 @@
-use crate::{style, thread_rng, Characters, Rng};
-use std::char;
+use crate::glyphs::GlyphId;
+use crate::{
+    style, Background, Color, GlyphTable, Gradient, LengthRange, Rng, SpeedDist, SpeedRange,
+    UserSettings,
+};
 use std::time::{Duration, Instant};
 
 /// Generates a single column of Characters.
-pub fn create_drop_chars(height: u16, group: &Characters) -> Vec<char> {
-    let g = group.as_vec_u32();
-    (0..height + 1)
-        .map(|_| char::from_u32(g[thread_rng().gen_range(0..g.len())]).unwrap_or('#'))
-        .collect()
+pub fn create_drop_chars<R: Rng>(
+    height: u16,
+    glyphs: &GlyphTable,
+    group: Option<usize>,
+    rng: &mut R,
+) -> Vec<GlyphId> {
+    let mut drop = vec![0; height as usize + 1];
+    glyphs.fill(&mut drop, group, rng);
+    drop
 }
 
 /// Generates all Characters in columns.
-pub fn charater_vecs(width: usize, height: u16, group: &Characters) -> Vec<Vec<char>> {
+pub fn charater_vecs<R: Rng>(
+    height: u16,
+    glyphs: &GlyphTable,
+    groups: &[Option<usize>],
+    rng: &mut R,
+) -> Vec<Vec<GlyphId>> {
+    groups
+        .iter()
+        .map(|&group| create_drop_chars(height, glyphs, group, rng))
+        .collect()
+}
+
+/// Picks the group of each column when every column sticks to one group.
+pub fn groups<R: Rng>(
+    width: usize,
+    glyphs: &GlyphTable,
+    per_column: bool,
+    rng: &mut R,
+) -> Vec<Option<usize>> {
     (0..width)
-        .map(|_| create_drop_chars(height, group))
+        .map(|_| glyphs.column_group(per_column, rng))
         .collect()
 }
 
+/// Builds the colors of a drop from its trail length, head first.
+pub type ColorFn = Box<dyn Fn(usize) -> Vec<style::Color>>;
+
 /// Generates the color function on startup to remove branching if statements from code.
-pub fn color_function(shading: bool) -> fn(style::Color, style::Color, u8) -> Vec<style::Color> {
+pub fn color_function(settings: &UserSettings) -> ColorFn {
     // This Creates a closure off of the args
     // given to the program at start that will crates the colors for the rain
-    match shading {
+    let base = base_color_function(settings);
+    match settings.background {
+        Background::Dark => base,
+        // Flipping lightness keeps the hues but turns the white head dark and makes
+        // shading fade towards the background instead of away from it.
+        Background::Light => Box::new(move |length: usize| {
+            base(length)
+                .into_iter()
+                .map(|color| match color {
+                    style::Color::Rgb { r, g, b } => Color::new(r, g, b).invert_lightness().into(),
+                    color => color,
+                })
+                .collect()
+        }),
+    }
+}
+
+fn base_color_function(settings: &UserSettings) -> ColorFn {
+    let head: style::Color = settings.head_color.into();
+    let rain = settings.rain_color;
+    let easing = settings.easing;
+    match (settings.gradient.clone(), settings.shading) {
+        // The head takes the first stop and the last cell of the trail the last stop
+        (Some(gradient), _) => {
+            Box::new(move |length: usize| gradient.colors(length, easing).map(Into::into).collect())
+        }
         // Creates shading colors
-        true => |bc: style::Color, head: style::Color, length: u8| {
-            let mut c: Vec<style::Color> = Vec::with_capacity(length as usize);
-            let (mut nr, mut ng, mut nb);
-            if let style::Color::Rgb { r, g, b } = bc {
-                for i in 0..length {
-                    nr = r / length;
-                    ng = g / length;
-                    nb = b / length;
-                    c.push((nr * i, ng * i, nb * i).into());
-                }
-                c.push(head);
-                c.reverse();
-            }
-            c
-        },
+        (None, true) => {
+            let fade = Gradient::new(vec![rain, Color::new(0, 0, 0)]).expect("two stops");
+            Box::new(move |length: usize| {
+                std::iter::once(head)
+                    .chain(fade.colors(length, easing).map(Into::into))
+                    .collect()
+            })
+        }
         // creates with out color
-        _ => |bc: style::Color, head: style::Color, length: u8| {
-            let mut c: Vec<style::Color> = Vec::with_capacity(length as usize);
-            c.push(head);
-            if let style::Color::Rgb { r, g, b } = bc {
-                for _ in 0..length {
-                    c.push((r, g, b).into());
-                }
-            }
-            c
-        },
+        (None, false) => Box::new(move |length: usize| {
+            std::iter::once(head)
+                .chain(std::iter::repeat_n(rain.into(), length))
+                .collect()
+        }),
     }
 }
 
-pub fn times(width: usize, (fastest, slowest): (u64, u64)) -> Vec<(Instant, Duration)> {
-    let now = Instant::now();
-    let mut rng = thread_rng();
+pub fn times<R: Rng>(
+    width: usize,
+    speed: SpeedRange,
+    dist: SpeedDist,
+    rng: &mut R,
+    now: Instant,
+) -> Vec<(Instant, Duration)> {
     (0..width.max(1))
-        .map(|_| (now, Duration::from_millis(rng.gen_range(fastest..slowest))))
+        .map(|_| (now, Duration::from_millis(dist.sample(speed, rng))))
         .collect()
 }
 
+/// Delay before a drop that has fallen `rows` rows moves again.
+///
+/// Gravity shortens `delay` the further the drop has fallen, so at `0.05` it moves
+/// twice as fast after 20 rows. Jitter stretches or shrinks each step by up to that
+/// fraction at random.
+pub fn step_delay<R: Rng>(
+    delay: Duration,
+    rows: usize,
+    gravity: f64,
+    jitter: f64,
+    rng: &mut R,
+) -> Duration {
+    if gravity == 0.0 && jitter == 0.0 {
+        return delay;
+    }
+    let mut scale = 1.0 / (1.0 + gravity * rows as f64);
+    if jitter > 0.0 {
+        scale *= rng.gen_range(1.0 - jitter..=1.0 + jitter);
+    }
+    delay.mul_f64(scale).max(Duration::from_millis(1))
+}
+
+/// About how long a drop is on a screen `height` rows tall, from its head coming in
+/// at the top until its tail leaves at the bottom, at the middle of the speed range
+/// and with gravity speeding it up.
+pub fn fall_time(height: usize, settings: &UserSettings) -> Duration {
+    let lengths = match settings.length {
+        Some(range) => range.rows(height),
+        None => 4..=(height.saturating_sub(10)).max(5) - 1,
+    };
+    let rows = height + (lengths.start() + lengths.end()) / 2;
+    let delay = (settings.speed.fastest() + settings.speed.slowest()) as f64 / 2.0;
+    let steps: f64 = (0..rows)
+        .map(|row| 1.0 / (1.0 + settings.gravity * row as f64))
+        .sum();
+    Duration::from_secs_f64(delay * steps / 1000.0)
+}
+
+/// How long a drop waits before it falls again.
+///
+/// Drops that start at random times leave a column empty `e^(-rate * fall)` of the
+/// time, so the `slots` drops of a column wait long enough between falls that it
+/// shows at least one of them about `density` of the time.
+pub fn spawn_wait<R: Rng>(fall: Duration, slots: usize, density: f64, rng: &mut R) -> Duration {
+    let fall = fall.as_secs_f64();
+    let rate = -(1.0 - density).ln() / fall;
+    let mean = (slots as f64 / rate - fall).max(0.0);
+    // Exponential waits keep the starts of a column random instead of bunched up.
+    let wait = mean * (1.0 / (1.0 - rng.gen::<f64>())).ln();
+    // Tiny densities wait practically forever, a day is as good and fits in an `Instant`.
+    Duration::from_secs_f64(wait.min(86_400.0))
+}
+
 /// Generates the visable length of each column.
-pub fn lengths(width: usize, height: usize) -> Vec<usize> {
-    let mut rng = thread_rng();
+pub fn lengths<R: Rng>(
+    width: usize,
+    height: usize,
+    range: Option<LengthRange>,
+    rng: &mut R,
+) -> Vec<usize> {
     (0..width.max(1))
-        .map(|_| rng.gen_range(4..(height.saturating_sub(10)).max(5)))
+        .map(|_| length(height, range, rng))
         .collect()
 }
 
-/// Uses Generates function to create all the color of the Rain/Characters.
-pub fn colors<F: Fn(style::Color, style::Color, u8) -> Vec<style::Color>>(
-    create_color: F,
-    head: (u8, u8, u8),
-    width: usize,
-    length: &[usize],
-    bc: style::Color,
-) -> Vec<Vec<style::Color>> {
-    let mut colors = Vec::with_capacity(width);
-    for l in length.iter() {
-        colors.push(create_color(bc, head.into(), *l as u8));
+/// Trail length of one drop, without a range from 4 up to 10 rows short of the screen.
+pub fn length<R: Rng>(height: usize, range: Option<LengthRange>, rng: &mut R) -> usize {
+    match range {
+        Some(range) => rng.gen_range(range.rows(height)),
+        None => rng.gen_range(4..(height.saturating_sub(10)).max(5)),
     }
-    colors
+}
+
+/// Uses Generates function to create all the color of the Rain/Characters.
+pub fn colors(create_color: &ColorFn, length: &[usize]) -> Vec<Vec<style::Color>> {
+    length.iter().map(|l| create_color(*l)).collect()
+}
+
+/// Spreads the columns around the color wheel when `rainbow` is on.
+pub fn hues(width: usize, rainbow: bool) -> Vec<f32> {
+    (0..width)
+        .map(|i| match rainbow {
+            true => i as f32 * 360.0 / width as f32,
+            false => 0.0,
+        })
+        .collect()
 }
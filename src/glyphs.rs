@@ -0,0 +1,244 @@
+use crate::{CharacterSet, Rng};
+use rand::distributions::{Distribution, Uniform, WeightedIndex};
+use std::collections::HashMap;
+use unicode_width::UnicodeWidthStr;
+
+/// Index of a glyph in a [`GlyphTable`].
+pub type GlyphId = u32;
+
+/// Every group of a character set interned once and picked from by weight.
+///
+/// A glyph is a whole grapheme cluster, so flags, skin tones and ZWJ sequences stay in
+/// one piece. Glyphs that take up no room on screen are dropped here instead of being
+/// checked on every pick, along with groups that end up empty.
+#[derive(Debug, Clone)]
+pub struct GlyphTable {
+    glyphs: Vec<String>,
+    /// Cells each glyph takes up, worked out once when the table is built.
+    widths: Vec<u16>,
+    groups: Vec<Vec<GlyphId>>,
+    /// Picks a position in each group, set up once so drawing a glyph is a single sample.
+    picks: Vec<Uniform<usize>>,
+    weights: WeightedIndex<u32>,
+    /// Cells every column is given, the width of the widest glyph.
+    width: u16,
+    /// The glyph after each one in the first group it shows up in, wrapping around.
+    next: Vec<GlyphId>,
+    /// Drops step through their group in order instead of picking at random.
+    sequence: bool,
+}
+
+impl GlyphTable {
+    pub fn new(set: &CharacterSet) -> Self {
+        Self::with_widths(set, &HashMap::new())
+    }
+
+    /// Builds the table trusting `widths` over the Unicode tables, for glyphs whose
+    /// width was measured on the terminal they will be drawn on.
+    pub fn with_widths(set: &CharacterSet, widths: &HashMap<String, u16>) -> Self {
+        let mut glyphs = Vec::new();
+        let mut glyph_widths = Vec::new();
+        let mut ids = HashMap::new();
+        let mut groups = Vec::new();
+        let mut weights = Vec::new();
+        for (group, weight) in set.parts() {
+            let group: Vec<GlyphId> = group
+                .into_iter()
+                .filter_map(|glyph| {
+                    let width = widths
+                        .get(&glyph)
+                        .copied()
+                        .unwrap_or_else(|| glyph_width(&glyph));
+                    if width == 0 {
+                        return None;
+                    }
+                    let id = *ids.entry(glyph.clone()).or_insert_with(|| {
+                        glyphs.push(glyph);
+                        glyph_widths.push(width);
+                        (glyphs.len() - 1) as GlyphId
+                    });
+                    Some(id)
+                })
+                .collect();
+            if !group.is_empty() && weight > 0 {
+                groups.push(group);
+                weights.push(weight);
+            }
+        }
+        let weights = WeightedIndex::new(weights).expect("character sets are never empty");
+        let picks = groups
+            .iter()
+            .map(|group| Uniform::new(0, group.len()))
+            .collect();
+        let width = glyph_widths.iter().copied().max().unwrap_or(1);
+        let mut next: Vec<Option<GlyphId>> = vec![None; glyphs.len()];
+        for group in groups.iter() {
+            for (&id, &after) in group.iter().zip(group.iter().cycle().skip(1)) {
+                next[id as usize].get_or_insert(after);
+            }
+        }
+        Self {
+            glyphs,
+            widths: glyph_widths,
+            groups,
+            picks,
+            weights,
+            width,
+            next: next
+                .into_iter()
+                .zip(0..)
+                .map(|(next, id)| next.unwrap_or(id))
+                .collect(),
+            sequence: false,
+        }
+    }
+
+    /// Lays drops out in the order of their group, starting anywhere in it, so the
+    /// head steps through the group as it falls.
+    pub fn sequence(mut self, sequence: bool) -> Self {
+        self.sequence = sequence;
+        self
+    }
+
+    /// Cells each column takes up on screen.
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    /// The text of a glyph.
+    pub fn glyph(&self, id: GlyphId) -> &str {
+        &self.glyphs[id as usize]
+    }
+
+    /// Cells a glyph takes up on screen.
+    pub fn glyph_width(&self, id: GlyphId) -> u16 {
+        self.widths[id as usize]
+    }
+
+    /// Every glyph in the table once.
+    pub fn glyphs(&self) -> impl Iterator<Item = &str> + '_ {
+        self.glyphs.iter().map(String::as_str)
+    }
+
+    /// Number of groups in the table.
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// Glyphs of one group.
+    pub fn group(&self, group: usize) -> &[GlyphId] {
+        &self.groups[group]
+    }
+
+    /// The group a column keeps drawing from, `None` picks a new group for every glyph.
+    pub fn column_group<R: Rng>(&self, per_column: bool, rng: &mut R) -> Option<usize> {
+        per_column.then(|| self.weights.sample(rng))
+    }
+
+    /// One glyph out of `group`, or out of any group by weight.
+    pub fn sample<R: Rng>(&self, group: Option<usize>, rng: &mut R) -> GlyphId {
+        let group = self
+            .only_group(group)
+            .unwrap_or_else(|| self.weights.sample(rng));
+        self.groups[group][self.picks[group].sample(rng)]
+    }
+
+    /// The group every glyph comes from when there is just one to choose.
+    fn only_group(&self, group: Option<usize>) -> Option<usize> {
+        match self.groups.len() {
+            1 => Some(0),
+            _ => group,
+        }
+    }
+
+    /// Overwrites every glyph in `glyphs` without allocating.
+    ///
+    /// The group is worked out once for the drop, only a mix without `--per-column`
+    /// picks one for every glyph. In sequence mode the whole drop comes from one
+    /// group, in order.
+    pub fn fill<R: Rng>(&self, glyphs: &mut [GlyphId], group: Option<usize>, rng: &mut R) {
+        match (self.sequence, self.only_group(group)) {
+            (false, Some(group)) => {
+                let (ids, pick) = (&self.groups[group], &self.picks[group]);
+                for glyph in glyphs {
+                    *glyph = ids[pick.sample(rng)];
+                }
+            }
+            (false, None) => {
+                for glyph in glyphs {
+                    *glyph = self.sample(None, rng);
+                }
+            }
+            (true, group) => {
+                let group = group.unwrap_or_else(|| self.weights.sample(rng));
+                let start = self.picks[group].sample(rng);
+                let ids = self.groups[group].iter().cycle().skip(start);
+                for (glyph, &id) in glyphs.iter_mut().zip(ids) {
+                    *glyph = id;
+                }
+            }
+        }
+    }
+
+    /// What a glyph changes into when it mutates, the next one along in sequence mode.
+    pub fn mutate<R: Rng>(&self, glyph: GlyphId, group: Option<usize>, rng: &mut R) -> GlyphId {
+        match self.sequence {
+            true => self.next[glyph as usize],
+            false => self.sample(group, rng),
+        }
+    }
+}
+
+/// East Asian width of a glyph, `0` for control characters and lone combining marks.
+pub fn glyph_width(glyph: &str) -> u16 {
+    match glyph.chars().any(char::is_control) {
+        true => 0,
+        false => glyph.width() as u16,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GlyphTable;
+    use crate::{CharacterSet, Characters, CustomChars};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn sequences_step_through_the_group_and_wrap() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let sets: [CharacterSet; 3] = [
+            Characters::Moon.into(),
+            Characters::Clock.into(),
+            "abcdefg".parse::<CustomChars>().unwrap().into(),
+        ];
+        for set in sets {
+            let table = GlyphTable::new(&set).sequence(true);
+            let group = table.group(0);
+            let glyphs: Vec<&str> = group.iter().map(|&id| table.glyph(id)).collect();
+            assert_eq!(glyphs, set.parts()[0].0, "{set} kept its order");
+            let after =
+                |id| group[(group.iter().position(|&g| g == id).unwrap() + 1) % group.len()];
+            for _ in 0..20 {
+                // Longer than any of the groups, so every drop wraps around.
+                let mut drop = vec![0; 60];
+                table.fill(&mut drop, None, &mut rng);
+                for pair in drop.windows(2) {
+                    assert_eq!(pair[1], after(pair[0]), "{set}");
+                }
+                assert!(
+                    drop.windows(2)
+                        .any(|pair| pair == [group[group.len() - 1], group[0]]),
+                    "{set} never wrapped"
+                );
+                for &glyph in &drop {
+                    assert_eq!(table.mutate(glyph, None, &mut rng), after(glyph), "{set}");
+                }
+            }
+        }
+    }
+}
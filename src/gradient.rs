@@ -0,0 +1,195 @@
+use crate::Color;
+use clap::ValueEnum;
+
+/// How quickly a trail moves from one end of its gradient to the other.
+#[derive(Debug, ValueEnum, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Easing {
+    #[default]
+    Linear,
+    /// Leaves the head quickly then lingers near the tail color, like phosphor fading.
+    Exponential,
+    /// Holds both ends and moves through the middle.
+    Smoothstep,
+}
+
+impl std::fmt::Display for Easing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            Self::Linear => "linear",
+            Self::Exponential => "exponential",
+            Self::Smoothstep => "smoothstep",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+impl Easing {
+    /// Maps `t` in `0.0..=1.0` onto the curve.
+    pub fn apply(&self, t: f32) -> f32 {
+        match self {
+            Self::Linear => t,
+            Self::Exponential => (1.0 - 2f32.powf(-10.0 * t)) / (1.0 - 2f32.powi(-10)),
+            Self::Smoothstep => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+/// Any number of colors spread evenly along a trail, blended in OKLab.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradient {
+    stops: Vec<Color>,
+}
+
+impl Gradient {
+    /// `None` if there are no stops.
+    pub fn new(stops: Vec<Color>) -> Option<Self> {
+        (!stops.is_empty()).then_some(Self { stops })
+    }
+
+    pub fn stops(&self) -> &[Color] {
+        &self.stops
+    }
+
+    /// The color `t` of the way along, `0.0` is the first stop and `1.0` the last.
+    pub fn sample(&self, t: f32, easing: Easing) -> Color {
+        let last = self.stops.len() - 1;
+        let position = easing.apply(t.clamp(0.0, 1.0)) * last as f32;
+        let i = (position.floor() as usize).min(last.saturating_sub(1));
+        match self.stops.get(i + 1) {
+            Some(next) => Oklab::from(self.stops[i])
+                .mix(Oklab::from(*next), position - i as f32)
+                .into(),
+            None => self.stops[i],
+        }
+    }
+
+    /// `count` colors running from the first stop to the last.
+    pub fn colors(&self, count: usize, easing: Easing) -> impl Iterator<Item = Color> + '_ {
+        let steps = count.saturating_sub(1).max(1) as f32;
+        (0..count).map(move |i| self.sample(i as f32 / steps, easing))
+    }
+}
+
+impl std::fmt::Display for Gradient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, stop) in self.stops.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{}", stop)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for Gradient {
+    type Err = crate::ParseColorError;
+
+    /// Colors separated by commas, commas inside `rgb(..)` or `hsl(..)` don't count.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut stops = Vec::new();
+        let (mut depth, mut start) = (0, 0);
+        for (i, c) in s.char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                ',' if depth == 0 => {
+                    stops.push(s[start..i].parse()?);
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        stops.push(s[start..].parse()?);
+        Ok(Self { stops })
+    }
+}
+
+/// A color in the OKLab space, where equal steps look equally far apart.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Oklab {
+    l: f32,
+    a: f32,
+    b: f32,
+}
+
+impl Oklab {
+    /// Turns the hue by `degrees` keeping lightness and chroma.
+    pub(crate) fn rotate(self, degrees: f32) -> Self {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        Self {
+            l: self.l,
+            a: self.a * cos - self.b * sin,
+            b: self.a * sin + self.b * cos,
+        }
+    }
+
+    /// Dark becomes light and light becomes dark, hue is kept.
+    pub(crate) fn invert(self) -> Self {
+        Self {
+            l: 1.0 - self.l,
+            ..self
+        }
+    }
+
+    pub(crate) fn lightness(self) -> f32 {
+        self.l
+    }
+
+    /// Squared distance, good enough for picking the closest color.
+    pub(crate) fn distance(self, other: Self) -> f32 {
+        (self.l - other.l).powi(2) + (self.a - other.a).powi(2) + (self.b - other.b).powi(2)
+    }
+
+    fn mix(self, other: Self, t: f32) -> Self {
+        Self {
+            l: self.l + (other.l - self.l) * t,
+            a: self.a + (other.a - self.a) * t,
+            b: self.b + (other.b - self.b) * t,
+        }
+    }
+}
+
+impl From<Color> for Oklab {
+    fn from(color: Color) -> Self {
+        let linear = |c: u8| {
+            let c = c as f32 / 255.0;
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        let (r, g, b) = (linear(color.r), linear(color.g), linear(color.b));
+        let l = (0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b).cbrt();
+        let m = (0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b).cbrt();
+        let s = (0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b).cbrt();
+        Self {
+            l: 0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+            a: 1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+            b: 0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+        }
+    }
+}
+
+impl From<Oklab> for Color {
+    fn from(Oklab { l, a, b }: Oklab) -> Self {
+        let cube = |v: f32| v * v * v;
+        let l_ = cube(l + 0.396_337_78 * a + 0.215_803_76 * b);
+        let m_ = cube(l - 0.105_561_346 * a - 0.063_854_17 * b);
+        let s_ = cube(l - 0.089_484_18 * a - 1.291_485_5 * b);
+        let srgb = |c: f32| {
+            let c = if c <= 0.003_130_8 {
+                12.92 * c
+            } else {
+                1.055 * c.powf(1.0 / 2.4) - 0.055
+            };
+            (c * 255.0).round().clamp(0.0, 255.0) as u8
+        };
+        Color::new(
+            srgb(4.076_741_7 * l_ - 3.307_711_6 * m_ + 0.230_969_94 * s_),
+            srgb(-1.268_438 * l_ + 2.609_757_4 * m_ - 0.341_319_38 * s_),
+            srgb(-0.004_196_086_3 * l_ - 0.703_418_6 * m_ + 1.707_614_7 * s_),
+        )
+    }
+}
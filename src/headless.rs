@@ -0,0 +1,28 @@
+use crate::cli::Cli;
+use crate::FRAME_MS;
+use crossterm::terminal;
+use rusty_rain::{FrameBuffer, RainEngine};
+use std::io::{stdout, BufWriter, Write};
+use std::time::Duration;
+
+/// Runs the rain against a `FrameBuffer` and writes each frame to stdout.
+///
+/// Never touches raw mode or the alternate screen so it works without a TTY.
+pub fn run(settings: &Cli) -> std::io::Result<()> {
+    let (width, height) = settings
+        .size
+        .or_else(|| terminal::size().ok())
+        .unwrap_or((80, 24));
+    let tick = Duration::from_millis(settings.fixed_clock.unwrap_or(FRAME_MS));
+    let mut engine = RainEngine::new(settings.user_settings(), width, height);
+    let mut screen = FrameBuffer::new(width, height);
+    let mut out = BufWriter::new(stdout().lock());
+
+    for frame in 0..settings.frames {
+        engine.step(engine.now() + tick);
+        engine.render(&mut screen)?;
+        writeln!(out, "--- frame {frame} ---")?;
+        write!(out, "{screen}")?;
+    }
+    out.flush()
+}
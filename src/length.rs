@@ -0,0 +1,218 @@
+/// One end of a [`LengthRange`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Length {
+    /// A number of rows.
+    Cells(usize),
+    /// A share of the screen height from `0.0` to `1.0`, along the direction of the rain.
+    Fraction(f64),
+}
+
+impl Length {
+    /// Rows this comes to on a screen `height` rows tall, at least 1.
+    pub fn rows(&self, height: usize) -> usize {
+        match self {
+            Self::Cells(rows) => *rows,
+            Self::Fraction(fraction) => (fraction * height as f64).round() as usize,
+        }
+        .max(1)
+    }
+}
+
+impl std::fmt::Display for Length {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Debug keeps the decimal point on whole fractions, so `1.0` doesn't read back as one row.
+        match self {
+            Self::Cells(rows) => write!(f, "{}", rows),
+            Self::Fraction(fraction) => write!(f, "{:?}", fraction),
+        }
+    }
+}
+
+impl std::str::FromStr for Length {
+    type Err = ParseLengthError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = s.trim();
+        if value.contains('.') {
+            match value.parse::<f64>() {
+                Ok(fraction) if fraction > 0.0 && fraction <= 1.0 => Ok(Self::Fraction(fraction)),
+                Ok(_) => Err(ParseLengthError::Fraction(value.into())),
+                Err(_) => Err(ParseLengthError::NotALength(value.into())),
+            }
+        } else {
+            match value.parse::<usize>() {
+                Ok(0) => Err(ParseLengthError::Zero),
+                Ok(rows) => Ok(Self::Cells(rows)),
+                Err(_) => Err(ParseLengthError::NotALength(value.into())),
+            }
+        }
+    }
+}
+
+/// Range of trail lengths of the drops.
+///
+/// Parses from `4,20`, `4..20`, fractions of the screen height like `0.1,0.5`, a mix
+/// of the two like `3,0.5`, or a single fixed length like `12`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LengthRange {
+    shortest: Length,
+    longest: Length,
+}
+
+impl LengthRange {
+    /// A range from `shortest` to `longest`, which can't be backwards when both are
+    /// rows or both are fractions.
+    pub fn new(shortest: Length, longest: Length) -> Result<Self, ParseLengthError> {
+        let backwards = match (shortest, longest) {
+            (Length::Cells(a), Length::Cells(b)) => a > b,
+            (Length::Fraction(a), Length::Fraction(b)) => a > b,
+            _ => false,
+        };
+        if backwards {
+            return Err(ParseLengthError::Inverted(
+                shortest.to_string(),
+                longest.to_string(),
+            ));
+        }
+        Ok(Self { shortest, longest })
+    }
+
+    /// Every drop is exactly `length` long.
+    pub fn fixed(length: Length) -> Self {
+        Self {
+            shortest: length,
+            longest: length,
+        }
+    }
+
+    pub fn shortest(&self) -> Length {
+        self.shortest
+    }
+
+    pub fn longest(&self) -> Length {
+        self.longest
+    }
+
+    /// Trail lengths in rows on a screen `height` rows tall. When one end is rows and
+    /// the other a fraction the longest is never shorter than the shortest.
+    pub fn rows(&self, height: usize) -> std::ops::RangeInclusive<usize> {
+        let shortest = self.shortest.rows(height);
+        shortest..=self.longest.rows(height).max(shortest)
+    }
+}
+
+impl std::fmt::Display for LengthRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.shortest == self.longest {
+            write!(f, "{}", self.shortest)
+        } else {
+            write!(f, "{},{}", self.shortest, self.longest)
+        }
+    }
+}
+
+/// Why a trail length could not be understood.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseLengthError {
+    NotALength(String),
+    Zero,
+    Fraction(String),
+    Inverted(String, String),
+}
+
+impl std::fmt::Display for ParseLengthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotALength(value) => write!(
+                f,
+                "'{value}' is not a length, expected MIN,MAX or one length as rows like 8 or a fraction of the screen like 0.5"
+            ),
+            Self::Zero => write!(f, "trails must be at least 1 row long"),
+            Self::Fraction(value) => write!(
+                f,
+                "'{value}' is not a fraction of the screen, it must be more than 0 and at most 1.0"
+            ),
+            Self::Inverted(shortest, longest) => write!(
+                f,
+                "{shortest},{longest} is backwards, the first length is the shortest so try {longest},{shortest}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ParseLengthError {}
+
+impl std::str::FromStr for LengthRange {
+    type Err = ParseLengthError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = s.trim();
+        match value.split_once("..").or_else(|| value.split_once(',')) {
+            Some((shortest, longest)) => Self::new(shortest.parse()?, longest.parse()?),
+            None => Ok(Self::fixed(value.parse()?)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Length, LengthRange, ParseLengthError};
+
+    #[test]
+    fn parses_rows_fractions_and_mixes() {
+        let cases = [
+            ("4,20", Length::Cells(4), Length::Cells(20)),
+            ("4..20", Length::Cells(4), Length::Cells(20)),
+            ("0.1,0.5", Length::Fraction(0.1), Length::Fraction(0.5)),
+            ("0.1..0.5", Length::Fraction(0.1), Length::Fraction(0.5)),
+            (" 3 , 0.5 ", Length::Cells(3), Length::Fraction(0.5)),
+            ("12", Length::Cells(12), Length::Cells(12)),
+            ("1.0", Length::Fraction(1.0), Length::Fraction(1.0)),
+        ];
+        for (input, shortest, longest) in cases {
+            let range = input.parse::<LengthRange>();
+            assert_eq!(range, LengthRange::new(shortest, longest), "{input}");
+        }
+    }
+
+    #[test]
+    fn rejects_bad_lengths() {
+        let cases = [
+            ("0", ParseLengthError::Zero),
+            ("0,5", ParseLengthError::Zero),
+            ("5,2", ParseLengthError::Inverted("5".into(), "2".into())),
+            (
+                "0.8,0.2",
+                ParseLengthError::Inverted("0.8".into(), "0.2".into()),
+            ),
+            ("1.5", ParseLengthError::Fraction("1.5".into())),
+            ("0.0", ParseLengthError::Fraction("0.0".into())),
+            ("long", ParseLengthError::NotALength("long".into())),
+            ("4,x", ParseLengthError::NotALength("x".into())),
+        ];
+        for (input, error) in cases {
+            assert_eq!(input.parse::<LengthRange>(), Err(error), "{input}");
+        }
+    }
+
+    #[test]
+    fn resolves_rows_on_a_screen() {
+        let cases = [
+            ("4,20", 50, 4..=20),
+            ("0.1,0.5", 40, 4..=20),
+            ("0.01", 40, 1..=1),
+            ("8,0.1", 40, 8..=8),
+        ];
+        for (input, height, rows) in cases {
+            let range: LengthRange = input.parse().unwrap();
+            assert_eq!(range.rows(height), rows, "{input}");
+        }
+    }
+
+    #[test]
+    fn displays_what_it_parses() {
+        for input in ["12", "4,20", "0.1,0.5", "1.0", "3,0.5"] {
+            assert_eq!(input.parse::<LengthRange>().unwrap().to_string(), input);
+        }
+    }
+}
@@ -0,0 +1,45 @@
+//! The simulation behind `rusty-rain`.
+//!
+//! [`RainEngine`] owns the rain state and advances it over time, so the effect can
+//! be embedded without going through the command line front end.
+pub mod background;
+pub mod characters;
+pub mod charset;
+pub mod color;
+pub mod depth;
+pub mod direction;
+mod engine;
+pub mod framebuffer;
+mod gen;
+pub mod glyphs;
+pub mod gradient;
+pub mod length;
+pub mod rain;
+pub mod speed;
+pub mod term;
+mod update;
+pub mod user_settings;
+
+// None Standard Crates
+use crossterm::{cursor, queue, style, terminal};
+use rand::Rng;
+
+// Modules
+pub use background::Background;
+pub use characters::Characters;
+pub use charset::{CharacterSet, CustomChars, Mix, ParseCharsError};
+pub use color::{Color, ParseColorError};
+pub use depth::ColorDepth;
+pub use direction::Direction;
+pub use engine::{Cell, RainEngine};
+pub use framebuffer::FrameBuffer;
+pub use glyphs::GlyphTable;
+pub use gradient::{Easing, Gradient};
+pub use length::{Length, LengthRange, ParseLengthError};
+pub use rain::Rain;
+pub use speed::{ParseSpeedError, SpeedDist, SpeedRange};
+pub use term::{CrosstermRenderer, Renderer};
+pub use user_settings::UserSettings;
+
+pub const MAXSPEED: u64 = 40;
+pub const MINSPEED: u64 = 200;
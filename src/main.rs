@@ -1,32 +1,26 @@
 // mod arguments;
-mod characters;
 mod cli;
-mod direction;
-mod gen;
-mod rain;
-mod term;
-mod update;
+mod config;
+mod gallery;
+mod headless;
+mod probe;
+mod theme;
 mod user_input;
 
 // None Standard Crates
-use clap::Parser;
-use crossterm::{cursor, execute, queue, style, terminal};
-use rand::{thread_rng, Rng};
+use crossterm::{cursor, execute, terminal};
+use rusty_rain::{CrosstermRenderer, RainEngine, Renderer};
 
 // Standard Library Crates
-use std::io::{stdout, Stdout, Write};
+use std::io::{stdout, Stdout};
+use std::time::{Duration, Instant};
 
 // Modules
 //use arguments::cargs;
-use characters::Characters;
-use direction::Direction;
-use rain::Rain;
-use term::{clear, draw};
-use update::{reset, update};
 use user_input::user_input;
 
-const MAXSPEED: u64 = 40;
-const MINSPEED: u64 = 200;
+/// Length of a frame when the clock is not the system clock.
+const FRAME_MS: u64 = 50;
 const AUTHOR: &str = "
 ▞▀▖       ▌        ▞▀▖▞▀▖▞▀▖▛▀▘
 ▌  ▞▀▖▌  ▌▛▀▖▞▀▖▌ ▌▚▄▘▙▄  ▗▘▙▄
@@ -36,45 +30,38 @@ Email: cowboy8625@protonmail.com
 ";
 
 struct App {
-    stdout: Stdout,
+    renderer: CrosstermRenderer<Stdout>,
     settings: cli::Cli,
 }
 
 impl App {
     fn new(settings: cli::Cli) -> Self {
         Self {
-            stdout: stdout(),
+            renderer: CrosstermRenderer::new(stdout()),
             settings,
         }
     }
     fn run(&mut self) -> std::io::Result<()> {
-        let (width, height) = match self.settings.direction {
-            Direction::Left | Direction::Right => {
-                let (w, h) = terminal::size()?;
-                (h, w)
-            }
-            Direction::Up | Direction::Down => terminal::size()?,
-        };
-
-        let create_color = gen::color_function(self.settings.shade);
-
-        let mut rain = Rain::new(create_color, width, height, &self.settings);
+        let (width, height) = terminal::size()?;
+        let mut engine = RainEngine::new(self.settings.user_settings(), width, height);
         let mut is_running = true;
 
         terminal::enable_raw_mode()?;
-        execute!(self.stdout, terminal::EnterAlternateScreen, cursor::Hide)?;
+        execute!(
+            self.renderer.writer_mut(),
+            terminal::EnterAlternateScreen,
+            cursor::Hide
+        )?;
 
         while is_running {
-            is_running = user_input(&mut self.stdout, &mut rain, &self.settings, create_color)?;
-            draw(
-                &mut self.stdout,
-                &rain,
-                self.settings.chars.width(),
-                &self.settings.direction,
-            )?;
-            self.stdout.flush()?;
-            update(&mut rain);
-            reset(create_color, &mut rain, &self.settings);
+            is_running = user_input(&mut self.renderer, &mut engine)?;
+            engine.render(&mut self.renderer)?;
+            self.renderer.flush()?;
+            let now = match self.settings.fixed_clock {
+                Some(ms) => engine.now() + Duration::from_millis(ms),
+                None => Instant::now(),
+            };
+            engine.step(now);
         }
         Ok(())
     }
@@ -82,13 +69,35 @@ impl App {
 
 impl Drop for App {
     fn drop(&mut self) {
-        execute!(self.stdout, cursor::Show, terminal::LeaveAlternateScreen)
-            .expect("failed to leave alternate screen");
+        execute!(
+            self.renderer.writer_mut(),
+            cursor::Show,
+            terminal::LeaveAlternateScreen
+        )
+        .expect("failed to leave alternate screen");
         terminal::disable_raw_mode().expect("failed to disable raw mode");
     }
 }
 
 fn main() -> std::io::Result<()> {
-    let settings = cli::Cli::parse();
+    let settings = cli::Cli::load();
+    if settings.list_themes {
+        print!("{}", theme::Theme::list());
+        return Ok(());
+    }
+    if settings.list_chars {
+        print!("{}", gallery::list());
+        return Ok(());
+    }
+    if let Some(group) = &settings.preview {
+        return gallery::preview(&settings, group);
+    }
+    if settings.print_config {
+        print!("{}", config::effective(&settings));
+        return Ok(());
+    }
+    if settings.headless {
+        return headless::run(&settings);
+    }
     App::new(settings).run()
 }
@@ -0,0 +1,282 @@
+use crossterm::terminal;
+use rusty_rain::{Background, CharacterSet, Characters, Color, GlyphTable};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// How long to wait on a terminal that may never answer.
+const TIMEOUT: Duration = Duration::from_millis(150);
+
+/// Most glyphs measured at startup, picked evenly from the whole set.
+const MAX_SAMPLES: usize = 256;
+
+/// Checks the terminal can show `set`, measuring how wide its glyphs really are.
+///
+/// Falls back to ASCII letters and numbers with a warning when the locale isn't UTF-8
+/// or the terminal draws glyphs in a way that can't be laid out in columns.
+pub fn glyphs(set: CharacterSet) -> (CharacterSet, HashMap<String, u16>) {
+    let table = GlyphTable::new(&set);
+    let glyphs: Vec<&str> = table.glyphs().filter(|glyph| !glyph.is_ascii()).collect();
+    if glyphs.is_empty() {
+        return (set, HashMap::new());
+    }
+    let fallback = |reason: &str| {
+        eprintln!("rusty-rain: {reason}, falling back to ASCII characters");
+        (CharacterSet::Builtin(Characters::AlphaNum), HashMap::new())
+    };
+    if !utf8_locale() {
+        return fallback("the locale is not UTF-8");
+    }
+    let step = glyphs.len().div_ceil(MAX_SAMPLES);
+    let sample: Vec<&str> = glyphs.into_iter().step_by(step).collect();
+    match measure(&sample) {
+        Some(widths) if !fits_columns(&widths) => {
+            fallback("the terminal does not draw these characters as 1 or 2 cells")
+        }
+        Some(widths) => (set, widths),
+        None => (set, HashMap::new()),
+    }
+}
+
+/// Glyphs can only be laid out in columns when each takes up 1 or 2 cells.
+fn fits_columns(widths: &HashMap<String, u16>) -> bool {
+    widths.values().all(|&width| width == 1 || width == 2)
+}
+
+/// The first of `LC_ALL`, `LC_CTYPE` and `LANG` that is set decides the encoding.
+#[cfg(unix)]
+fn utf8_locale() -> bool {
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .map(|locale| locale.to_ascii_lowercase().replace('-', ""))
+        .is_some_and(|locale| locale.contains("utf8"))
+}
+
+/// The Windows console speaks UTF-16 whatever the locale.
+#[cfg(not(unix))]
+fn utf8_locale() -> bool {
+    true
+}
+
+/// Prints each glyph at the start of the line and asks where the cursor ended up.
+fn measure(glyphs: &[&str]) -> Option<HashMap<String, u16>> {
+    let mut request: String = glyphs
+        .iter()
+        .map(|glyph| format!("\r{glyph}\x1b[6n"))
+        .collect();
+    // Wipe the samples off the line, then end with device attributes like OSC 11.
+    request.push_str("\r\x1b[2K\x1b[c");
+    let reply = query(&request, answered)?;
+    read_widths(glyphs, &String::from_utf8_lossy(&reply))
+}
+
+/// Width of each glyph from the column the cursor ended up in after it, `None` unless
+/// every glyph got an answer.
+fn read_widths(glyphs: &[&str], reply: &str) -> Option<HashMap<String, u16>> {
+    let columns = parse_cursor_positions(reply);
+    (columns.len() == glyphs.len()).then(|| {
+        glyphs
+            .iter()
+            .zip(columns)
+            .map(|(&glyph, column)| (glyph.to_string(), column.saturating_sub(1)))
+            .collect()
+    })
+}
+
+/// Columns out of every `ESC [ row ; column R` in the reply.
+fn parse_cursor_positions(reply: &str) -> Vec<u16> {
+    reply
+        .split('\x1b')
+        .filter_map(|report| {
+            let (_, column) = report
+                .strip_prefix('[')?
+                .strip_suffix('R')?
+                .split_once(';')?;
+            column.parse().ok()
+        })
+        .collect()
+}
+
+/// Asks the terminal for its background color with OSC 11.
+///
+/// Falls back to `COLORFGBG` and then to dark when there is no answer in time.
+pub fn background() -> Background {
+    query_background()
+        .or_else(Background::from_env)
+        .unwrap_or_default()
+}
+
+fn query_background() -> Option<Background> {
+    let reply = query("\x1b]11;?\x07\x1b[c", answered)?;
+    parse_osc11(&String::from_utf8_lossy(&reply)).map(Color::background)
+}
+
+/// Whether the reply to primary device attributes, `ESC [ ? ... c`, has come in.
+///
+/// Every terminal answers it, so once it shows up the replies to anything asked
+/// before it have too and nothing is left over to be read as a key press.
+fn answered(reply: &[u8]) -> bool {
+    reply
+        .windows(3)
+        .position(|w| w == b"\x1b[?")
+        .is_some_and(|start| reply[start..].contains(&b'c'))
+}
+
+/// Pulls the color out of `ESC ] 11 ; rgb:RRRR/GGGG/BBBB`.
+fn parse_osc11(reply: &str) -> Option<Color> {
+    let start = reply.find("]11;rgb:")? + "]11;rgb:".len();
+    let body = &reply[start..];
+    let end = body.find(['\x07', '\x1b']).unwrap_or(body.len());
+    let mut channels = body[..end].split('/').map(|hex| {
+        let value = u32::from_str_radix(hex, 16).ok()?;
+        let max = 16u32.checked_pow(hex.len() as u32)?.checked_sub(1)?;
+        Some((value * 255 / max.max(1)) as u8)
+    });
+    Some(Color::new(
+        channels.next()??,
+        channels.next()??,
+        channels.next()??,
+    ))
+}
+
+/// Writes `request` to the terminal in raw mode and reads until `done` says the
+/// reply is complete or the timeout runs out.
+#[cfg(unix)]
+pub fn query(request: &str, done: impl Fn(&[u8]) -> bool) -> Option<Vec<u8>> {
+    use std::fs::OpenOptions;
+    use std::io::{Read, Write};
+    use std::os::fd::AsRawFd;
+    use std::time::Instant;
+
+    let mut tty = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .ok()?;
+    let was_raw = terminal::is_raw_mode_enabled().ok()?;
+    terminal::enable_raw_mode().ok()?;
+    let reply = (|| {
+        tty.write_all(request.as_bytes()).ok()?;
+        tty.flush().ok()?;
+        let deadline = Instant::now() + TIMEOUT;
+        let mut reply = Vec::new();
+        let mut buffer = [0u8; 256];
+        while !done(&reply) {
+            let left = deadline.saturating_duration_since(Instant::now());
+            let mut fd = libc::pollfd {
+                fd: tty.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            // SAFETY: `fd` is a single valid pollfd that lives for the whole call.
+            if left.is_zero() || unsafe { libc::poll(&mut fd, 1, left.as_millis() as i32) } <= 0 {
+                return None;
+            }
+            let read = tty.read(&mut buffer).ok()?;
+            reply.extend_from_slice(&buffer[..read]);
+        }
+        Some(reply)
+    })();
+    if !was_raw {
+        terminal::disable_raw_mode().ok()?;
+    }
+    reply
+}
+
+/// Reading the console without blocking isn't worth it on other platforms.
+#[cfg(not(unix))]
+pub fn query(_request: &str, _done: impl Fn(&[u8]) -> bool) -> Option<Vec<u8>> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{answered, fits_columns, parse_cursor_positions, parse_osc11, read_widths};
+    use rusty_rain::Color;
+    use std::collections::HashMap;
+
+    #[test]
+    fn reads_the_background_color() {
+        let cases = [
+            (
+                "\x1b]11;rgb:ffff/0000/8080\x07",
+                Some(Color::new(255, 0, 128)),
+            ),
+            ("\x1b]11;rgb:ff/00/80\x1b\\", Some(Color::new(255, 0, 128))),
+            ("\x1b]11;rgb:f/0/8\x07", Some(Color::new(255, 0, 136))),
+            (
+                "\x1b]11;rgb:1e1e/1e1e/2e2e\x1b\\\x1b[?62;22c",
+                Some(Color::new(30, 30, 46)),
+            ),
+            ("\x1b[?62;22c", None),
+            ("\x1b]11;rgb:ffff/ffff\x07", None),
+            ("\x1b]11;rgb:ffff/ffff/\x07", None),
+            ("\x1b]11;rgb:zzzz/0000/0000\x07", None),
+            ("\x1b]11;rgb:", None),
+            ("garbage", None),
+        ];
+        for (reply, color) in cases {
+            assert_eq!(parse_osc11(reply), color, "{reply:?}");
+        }
+    }
+
+    #[test]
+    fn waits_for_device_attributes() {
+        let cases: [(&[u8], bool); 5] = [
+            (b"\x1b]11;rgb:0000/0000/0000\x07\x1b[?62;22c", true),
+            (b"\x1b[?1;2c", true),
+            (b"\x1b]11;rgb:0000/0000/0000\x07\x1b[?62;2", false),
+            (b"\x1b[1;3R", false),
+            (b"", false),
+        ];
+        for (reply, done) in cases {
+            assert_eq!(answered(reply), done, "{reply:?}");
+        }
+    }
+
+    #[test]
+    fn reads_cursor_positions() {
+        let cases: [(&str, &[u16]); 6] = [
+            ("\x1b[5;2R\x1b[5;3R", &[2, 3]),
+            ("\x1b[12;40R", &[40]),
+            ("\x1b[5;2R\x1b[5;", &[2]),
+            ("\x1b[5;2R\x1b[?62;22c\x1b[5;3R", &[2, 3]),
+            ("\x1b[5;xR\x1b[5R\x1b[?1;2c", &[]),
+            ("", &[]),
+        ];
+        for (reply, columns) in cases {
+            assert_eq!(parse_cursor_positions(reply), columns, "{reply:?}");
+        }
+    }
+
+    #[test]
+    fn measures_each_glyph_or_falls_back() {
+        let glyphs = ["カ", "🌕"];
+        // The reply, the widths read from it and whether they make it fall back to ASCII.
+        let cases: [(&str, Option<[u16; 2]>, bool); 6] = [
+            ("\x1b[1;3R\x1b[1;3R\x1b[?62;22c", Some([2, 2]), false),
+            ("\x1b[1;3R\x1b[?62;22c\x1b[1;2R", Some([2, 1]), false),
+            ("\x1b[1;3R\x1b[?62;22c", None, false),
+            ("\x1b[?62;22c", None, false),
+            ("\x1b[1;3R\x1b[1;1R\x1b[?62;22c", Some([2, 0]), true),
+            ("\x1b[1;3R\x1b[1;5R\x1b[?62;22c", Some([2, 4]), true),
+        ];
+        for (reply, widths, fallback) in cases {
+            let read = read_widths(&glyphs, reply);
+            let expected = widths.map(|widths| {
+                glyphs
+                    .iter()
+                    .map(|glyph| glyph.to_string())
+                    .zip(widths)
+                    .collect::<HashMap<_, _>>()
+            });
+            assert_eq!(read, expected, "{reply:?}");
+            assert_eq!(
+                read.is_some_and(|read| !fits_columns(&read)),
+                fallback,
+                "{reply:?}"
+            );
+        }
+    }
+}
@@ -1,37 +1,79 @@
-use crate::cli::Cli;
-use crate::{gen, style};
+use crate::gen::{self, ColorFn};
+use crate::glyphs::GlyphId;
+use crate::{style, Color, GlyphTable, Rng, UserSettings};
 use std::time::{Duration, Instant};
 
 #[derive(Debug)]
 pub struct Rain {
-    pub charaters: Vec<Vec<char>>,
+    /// Glyph of every row of every drop, looked up in `glyphs`.
+    pub charaters: Vec<Vec<GlyphId>>,
+    /// Row of the head of every drop.
     pub locations: Vec<usize>,
+    /// Rows of trail of every drop, `0` for a drop that is waiting to fall.
     pub length: Vec<usize>,
     pub colors: Vec<Vec<style::Color>>,
+    /// When every drop moves next and its delay before gravity and jitter.
     pub time: Vec<(Instant, Duration)>,
+    /// Drops that moved or started falling this step.
     pub queue: Vec<usize>,
     pub height: u16,
+    /// Drops each column has room for, drop `i` falls down column `i / slots`.
+    pub slots: usize,
+    /// Hue turn in degrees of each column.
+    pub hues: Vec<f32>,
+    /// Hue turn in degrees added to every column.
+    pub hue_shift: f32,
+    /// Every column gets drawn next frame, not just the ones in `queue`.
+    pub repaint: bool,
+    /// Cells as `(drop, row)` whose glyph changed in place this step.
+    pub mutated: Vec<(usize, usize)>,
+    /// Group of the character set each column draws from, `None` draws from all of them.
+    pub groups: Vec<Option<usize>>,
+    /// Glyphs every drop and mutation is picked from.
+    pub glyphs: GlyphTable,
 }
 
 impl Rain {
-    pub fn new<F>(create_color: F, width: u16, height: u16, settings: &Cli) -> Self
-    where
-        F: Fn(style::Color, style::Color, u8) -> Vec<style::Color>,
-    {
-        let w = (width / settings.chars.width()) as usize;
+    /// Creates `width` columns of rain that are `height` rows tall.
+    pub fn new<R: Rng>(
+        create_color: &ColorFn,
+        glyphs: GlyphTable,
+        width: u16,
+        height: u16,
+        settings: &UserSettings,
+        rng: &mut R,
+        now: Instant,
+    ) -> Self {
+        let w = width as usize;
         let h = height as usize;
-        let charaters = gen::charater_vecs(w, height, &settings.chars);
-        let locations = vec![0; w];
-        let length = gen::lengths(w, h);
-        let colors = gen::colors(
-            create_color,
-            settings.head_color(),
-            w,
-            &length,
-            settings.rain_color().into(),
-        );
-        let time = gen::times(w, settings.speed());
-        let queue = Vec::with_capacity(w);
+        // Every drop needs at least 5 rows, 4 of trail and a gap.
+        let slots = match settings.density {
+            Some(_) => h / 5 + 2,
+            None => 1,
+        };
+        let drops = w * slots;
+        let groups = gen::groups(w, &glyphs, settings.per_column, rng);
+        let drop_groups: Vec<Option<usize>> = groups
+            .iter()
+            .flat_map(|&group| std::iter::repeat_n(group, slots))
+            .collect();
+        let charaters = gen::charater_vecs(height, &glyphs, &drop_groups, rng);
+        let locations = vec![0; drops];
+        let mut length = gen::lengths(drops, h, settings.length, rng);
+        let colors = gen::colors(create_color, &length);
+        let mut time = gen::times(drops, settings.speed, settings.speed_dist, rng, now);
+        let queue = Vec::with_capacity(drops);
+        let hues = gen::hues(w, settings.rainbow);
+        // Only the first drop of some columns falls straight away, the rest wait their turn.
+        if let Some(density) = settings.density {
+            let fall = gen::fall_time(h, settings);
+            for drop in 0..length.len() {
+                if drop % slots != 0 || !rng.gen_bool(density) {
+                    length[drop] = 0;
+                    time[drop].0 = now + gen::spawn_wait(fall, slots, density, rng);
+                }
+            }
+        }
         Self {
             charaters,
             locations,
@@ -40,6 +82,66 @@ impl Rain {
             time,
             queue,
             height,
+            slots,
+            hues,
+            hue_shift: 0.0,
+            repaint: false,
+            mutated: Vec::new(),
+            groups,
+            glyphs,
+        }
+    }
+}
+
+impl Rain {
+    /// Visible rows of a drop as `(row, glyph, color)`.
+    ///
+    /// Row `r` holds `charaters[r]` and is `location - r` steps behind the head.
+    pub fn visible(&self, drop: usize) -> impl Iterator<Item = (usize, &str, style::Color)> + '_ {
+        self.rows(drop)
+            .map(move |row| (row, self.glyph(drop, row), self.color(drop, row)))
+    }
+
+    /// Column a drop falls down.
+    pub fn column(&self, drop: usize) -> usize {
+        drop / self.slots
+    }
+
+    /// Text of the glyph in a cell.
+    pub fn glyph(&self, drop: usize, row: usize) -> &str {
+        self.glyphs.glyph(self.charaters[drop][row])
+    }
+
+    /// Rows of a drop that currently show a glyph.
+    pub fn rows(&self, drop: usize) -> std::ops::RangeInclusive<usize> {
+        let location = self.locations[drop];
+        (location + 1).saturating_sub(self.length[drop])..=location.min(self.height as usize)
+    }
+
+    /// Empty rows between a head at `head` and the tail of the nearest other drop
+    /// below it in the same column.
+    pub fn clearance(&self, drop: usize, head: usize) -> usize {
+        let column = self.column(drop);
+        (column * self.slots..(column + 1) * self.slots)
+            .filter(|&other| {
+                other != drop && self.length[other] > 0 && self.locations[other] >= head
+            })
+            .map(|other| {
+                let tail = (self.locations[other] + 1).saturating_sub(self.length[other]);
+                tail.saturating_sub(head + 1)
+            })
+            .min()
+            .unwrap_or(usize::MAX)
+    }
+
+    /// Color of a visible cell with any hue turn applied.
+    pub fn color(&self, drop: usize, row: usize) -> style::Color {
+        let hue = self.hues[self.column(drop)] + self.hue_shift;
+        match self.colors[drop][self.locations[drop] - row] {
+            style::Color::Rgb { r, g, b } if hue != 0.0 => {
+                Color::new(r, g, b).rotate_hue(hue).into()
+            }
+            color => color,
         }
     }
 }
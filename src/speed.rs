@@ -0,0 +1,293 @@
+use crate::{Rng, MAXSPEED, MINSPEED};
+use clap::ValueEnum;
+
+/// Range of delays in milliseconds between each step of a drop.
+///
+/// Parses from `fast`, `normal`, `slow`, `80..200`, `80,200` or a single fixed delay like `100`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpeedRange {
+    fastest: u64,
+    slowest: u64,
+}
+
+impl SpeedRange {
+    pub const FAST: Self = Self {
+        fastest: 20,
+        slowest: 100,
+    };
+    pub const NORMAL: Self = Self {
+        fastest: MAXSPEED,
+        slowest: MINSPEED,
+    };
+    pub const SLOW: Self = Self {
+        fastest: 100,
+        slowest: 400,
+    };
+
+    /// A range from `fastest` to `slowest` milliseconds, both at least 1.
+    pub fn new(fastest: u64, slowest: u64) -> Result<Self, ParseSpeedError> {
+        if fastest == 0 {
+            return Err(ParseSpeedError::Zero);
+        }
+        if fastest > slowest {
+            return Err(ParseSpeedError::Inverted(fastest, slowest));
+        }
+        Ok(Self { fastest, slowest })
+    }
+
+    /// Every drop waits exactly `delay` milliseconds.
+    pub fn fixed(delay: u64) -> Result<Self, ParseSpeedError> {
+        Self::new(delay, delay)
+    }
+
+    pub fn fastest(&self) -> u64 {
+        self.fastest
+    }
+
+    pub fn slowest(&self) -> u64 {
+        self.slowest
+    }
+
+    pub fn range(&self) -> std::ops::RangeInclusive<u64> {
+        self.fastest..=self.slowest
+    }
+}
+
+/// How delays are spread over a [`SpeedRange`].
+#[derive(Debug, ValueEnum, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SpeedDist {
+    /// Any delay in the range is as likely as any other.
+    #[default]
+    Uniform,
+    /// Most drops fall at about the middle of the range, a few much faster or slower.
+    Normal,
+    /// Drops are mostly either fast or slow, few fall in between.
+    Bimodal,
+    /// Every drop falls at the middle of the range.
+    Fixed,
+}
+
+impl std::fmt::Display for SpeedDist {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            Self::Uniform => "uniform",
+            Self::Normal => "normal",
+            Self::Bimodal => "bimodal",
+            Self::Fixed => "fixed",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+impl SpeedDist {
+    /// A delay in milliseconds from `speed`, never outside of it.
+    pub fn sample<R: Rng>(&self, speed: SpeedRange, rng: &mut R) -> u64 {
+        let (fastest, slowest) = (speed.fastest as f64, speed.slowest as f64);
+        let span = slowest - fastest;
+        let delay = match self {
+            Self::Uniform => return rng.gen_range(speed.range()),
+            Self::Normal => normal((fastest + slowest) / 2.0, span / 6.0, rng),
+            Self::Bimodal => match rng.gen_bool(0.5) {
+                true => normal(fastest + span / 6.0, span / 12.0, rng),
+                false => normal(slowest - span / 6.0, span / 12.0, rng),
+            },
+            Self::Fixed => (fastest + slowest) / 2.0,
+        };
+        (delay.round() as u64).clamp(speed.fastest, speed.slowest)
+    }
+}
+
+/// A draw from a normal distribution, by the Box-Muller transform.
+fn normal<R: Rng>(mean: f64, deviation: f64, rng: &mut R) -> f64 {
+    let radius = (-2.0 * rng.gen_range(f64::EPSILON..1.0).ln()).sqrt();
+    let angle = std::f64::consts::TAU * rng.gen::<f64>();
+    mean + deviation * radius * angle.cos()
+}
+
+impl Default for SpeedRange {
+    fn default() -> Self {
+        Self::NORMAL
+    }
+}
+
+impl std::fmt::Display for SpeedRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.fastest == self.slowest {
+            write!(f, "{}", self.fastest)
+        } else {
+            write!(f, "{}..{}", self.fastest, self.slowest)
+        }
+    }
+}
+
+/// Why a speed could not be understood.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseSpeedError {
+    NotANumber(String),
+    Zero,
+    Inverted(u64, u64),
+    Equal(u64),
+}
+
+impl std::fmt::Display for ParseSpeedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotANumber(value) => write!(
+                f,
+                "'{value}' is not a speed, expected fast, normal, slow, MIN..MAX or a delay in milliseconds"
+            ),
+            Self::Zero => write!(f, "delays must be at least 1 millisecond"),
+            Self::Inverted(fastest, slowest) => write!(
+                f,
+                "{fastest}..{slowest} is backwards, the first number is the shortest delay so try {slowest}..{fastest}"
+            ),
+            Self::Equal(delay) => write!(
+                f,
+                "{delay}..{delay} is empty, use {delay} on its own for a fixed speed"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ParseSpeedError {}
+
+impl std::str::FromStr for SpeedRange {
+    type Err = ParseSpeedError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = s.trim();
+        let number = |n: &str| {
+            n.trim()
+                .parse::<u64>()
+                .map_err(|_| ParseSpeedError::NotANumber(value.into()))
+        };
+        match value.to_ascii_lowercase().as_str() {
+            "fast" => return Ok(Self::FAST),
+            "normal" => return Ok(Self::NORMAL),
+            "slow" => return Ok(Self::SLOW),
+            _ => {}
+        }
+        match value.split_once("..").or_else(|| value.split_once(',')) {
+            Some((fastest, slowest)) => {
+                let (fastest, slowest) = (number(fastest)?, number(slowest)?);
+                if fastest == slowest {
+                    return Err(ParseSpeedError::Equal(fastest));
+                }
+                Self::new(fastest, slowest)
+            }
+            None => Self::fixed(number(value)?),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ParseSpeedError, SpeedDist, SpeedRange};
+    use crate::gen::step_delay;
+    use clap::ValueEnum;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::time::Duration;
+
+    #[test]
+    fn parses_presets_ranges_and_fixed_delays() {
+        let cases = [
+            ("fast", SpeedRange::FAST),
+            ("Normal", SpeedRange::NORMAL),
+            ("slow", SpeedRange::SLOW),
+            ("80..200", SpeedRange::new(80, 200).unwrap()),
+            (" 80 , 200 ", SpeedRange::new(80, 200).unwrap()),
+            ("100", SpeedRange::fixed(100).unwrap()),
+        ];
+        for (input, speed) in cases {
+            assert_eq!(input.parse::<SpeedRange>(), Ok(speed), "{input}");
+        }
+    }
+
+    #[test]
+    fn rejects_bad_speeds() {
+        let cases = [
+            ("0", ParseSpeedError::Zero),
+            ("0..10", ParseSpeedError::Zero),
+            ("300,100", ParseSpeedError::Inverted(300, 100)),
+            ("5..5", ParseSpeedError::Equal(5)),
+            ("quick", ParseSpeedError::NotANumber("quick".into())),
+            ("1..x", ParseSpeedError::NotANumber("1..x".into())),
+        ];
+        for (input, error) in cases {
+            assert_eq!(input.parse::<SpeedRange>(), Err(error), "{input}");
+        }
+    }
+
+    #[test]
+    fn displays_what_it_parses() {
+        for input in ["100", "80..200"] {
+            assert_eq!(input.parse::<SpeedRange>().unwrap().to_string(), input);
+        }
+    }
+
+    #[test]
+    fn samples_stay_inside_the_range() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let speeds = [
+            SpeedRange::FAST,
+            SpeedRange::SLOW,
+            SpeedRange::new(1, 2).unwrap(),
+            SpeedRange::fixed(100).unwrap(),
+        ];
+        for dist in SpeedDist::value_variants() {
+            for speed in speeds {
+                for _ in 0..5000 {
+                    let delay = dist.sample(speed, &mut rng);
+                    assert!(
+                        speed.range().contains(&delay),
+                        "{dist} {speed} gave {delay}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn gravity_speeds_drops_up_as_they_fall() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let delay = Duration::from_millis(100);
+        let cases = [(0, 100), (20, 50), (60, 25), (180, 10)];
+        for (rows, millis) in cases {
+            assert_eq!(
+                step_delay(delay, rows, 0.05, 0.0, &mut rng),
+                Duration::from_millis(millis),
+                "after {rows} rows"
+            );
+        }
+        let delays: Vec<_> = (0..100)
+            .map(|rows| step_delay(delay, rows, 0.5, 0.0, &mut rng))
+            .collect();
+        assert!(delays.windows(2).all(|pair| pair[1] <= pair[0]));
+        assert!(delays
+            .iter()
+            .all(|&delay| delay >= Duration::from_millis(1)));
+        assert_eq!(step_delay(delay, 50, 0.0, 0.0, &mut rng), delay);
+    }
+
+    #[test]
+    fn jitter_stays_within_its_bound() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let delay = Duration::from_millis(100);
+        for jitter in [0.1, 0.5, 1.0] {
+            let delays: Vec<_> = (0..5000)
+                .map(|_| step_delay(delay, 0, 0.0, jitter, &mut rng))
+                .collect();
+            let (shortest, longest) = (delay.mul_f64(1.0 - jitter), delay.mul_f64(1.0 + jitter));
+            for &step in &delays {
+                assert!(
+                    step >= shortest.max(Duration::from_millis(1)) && step <= longest,
+                    "{step:?} is outside {jitter} of {delay:?}"
+                );
+            }
+            // It does move the delay both ways, not just stay put.
+            assert!(delays.iter().any(|&step| step < delay));
+            assert!(delays.iter().any(|&step| step > delay));
+        }
+    }
+}
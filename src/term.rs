@@ -1,86 +1,248 @@
-use crate::{cursor, queue, style, terminal, Direction, Rain, Stdout};
+use crate::{cursor, queue, style, terminal, Rain, UserSettings};
+use itertools::Either;
+use std::io::Write;
 
-pub fn clear(w: &mut Stdout) -> std::io::Result<()> {
-    queue!(w, terminal::Clear(terminal::ClearType::All))?;
-    Ok(())
+/// Something that rain cell updates can be drawn onto.
+///
+/// Positions are screen coordinates, the direction of the rain is already applied.
+pub trait Renderer {
+    /// Places `glyph` at `x`, `y` in the given color and attributes.
+    ///
+    /// A glyph is a whole grapheme cluster, a flag or ZWJ sequence is one glyph.
+    fn draw_glyph(
+        &mut self,
+        x: u16,
+        y: u16,
+        glyph: &str,
+        style: style::ContentStyle,
+    ) -> std::io::Result<()>;
+    /// Blanks `width` cells starting at `x`, `y`.
+    fn erase(&mut self, x: u16, y: u16, width: u16) -> std::io::Result<()>;
+    /// Blanks the whole screen.
+    fn clear(&mut self) -> std::io::Result<()>;
+    /// Pushes out anything that has been buffered.
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Renders through crossterm commands queued on any writer, usually `Stdout`.
+#[derive(Debug)]
+pub struct CrosstermRenderer<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> CrosstermRenderer<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    pub fn writer_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+}
+
+impl<W: Write> Renderer for CrosstermRenderer<W> {
+    fn draw_glyph(
+        &mut self,
+        x: u16,
+        y: u16,
+        glyph: &str,
+        style: style::ContentStyle,
+    ) -> std::io::Result<()> {
+        queue!(self.writer, cursor::MoveTo(x, y))?;
+        match style.foreground_color.map(|color| (color, sgr16(color))) {
+            // crossterm writes named colors as palette indexes, which the Linux console
+            // and other 16 color terminals don't understand.
+            Some((_, Some(code))) => write!(self.writer, "\x1b[{}m", code)?,
+            Some((color, None)) => queue!(self.writer, style::SetForegroundColor(color))?,
+            None => {}
+        }
+        if style.attributes.is_empty() {
+            queue!(self.writer, style::Print(glyph))
+        } else {
+            queue!(
+                self.writer,
+                style::SetAttributes(style.attributes),
+                style::Print(glyph),
+                style::SetAttribute(style::Attribute::Reset),
+            )
+        }
+    }
+
+    fn erase(&mut self, x: u16, y: u16, width: u16) -> std::io::Result<()> {
+        queue!(
+            self.writer,
+            cursor::MoveTo(x, y),
+            style::Print(" ".repeat(width as usize)),
+        )
+    }
+
+    fn clear(&mut self) -> std::io::Result<()> {
+        queue!(self.writer, terminal::Clear(terminal::ClearType::All))
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// The classic SGR foreground code of the 16 named colors.
+fn sgr16(color: style::Color) -> Option<u8> {
+    use style::Color::*;
+    let code = match color {
+        Black => 30,
+        DarkRed => 31,
+        DarkGreen => 32,
+        DarkYellow => 33,
+        DarkBlue => 34,
+        DarkMagenta => 35,
+        DarkCyan => 36,
+        Grey => 37,
+        DarkGrey => 90,
+        Red => 91,
+        Green => 92,
+        Yellow => 93,
+        Blue => 94,
+        Magenta => 95,
+        Cyan => 96,
+        White => 97,
+        _ => return None,
+    };
+    Some(code)
 }
 
-// TODO: Clean this crap up
 // Draw takes rain data and places it on screen.
-pub fn draw(
-    w: &mut Stdout,
+pub fn draw<R: Renderer>(
+    renderer: &mut R,
     rain: &Rain,
-    spacing: u16,
-    direction: &Direction,
+    settings: &UserSettings,
 ) -> std::io::Result<()> {
-    // NOTE: Maybe move this into its own functions to be generated at startup
-    // to lessen the amount of branching done.
-    // Further investigation into the assembly code to see if this is worth it.
-    use Direction::*;
-    // Since we do not keep track of the x and y value of the rain we need to swap
-    // values depending on desired direction.
-    let move_to = match direction {
-        Down => |x: u16, y: u16, _: u16| cursor::MoveTo(x, y),
-        Up => |x: u16, y: u16, offest: u16| cursor::MoveTo(x, offest - y),
-        Right => |x: u16, y: u16, _: u16| cursor::MoveTo(y, x),
-        Left => |x: u16, y: u16, offest: u16| cursor::MoveTo(offest - y, x),
+    let spacing = rain.glyphs.width();
+    let direction = settings.direction;
+    let height = rain.height as usize;
+    let mut draw_cell = |drop: usize, row: usize, color| {
+        let id = rain.charaters[drop][row];
+        let column = rain.column(drop) as u16;
+        let (x, y) = direction.to_screen(column, row as u16, rain.height, spacing);
+        let style = settings.color_depth.style(
+            color,
+            rain.locations[drop] - row,
+            settings.dither,
+            settings.background,
+        );
+        renderer.draw_glyph(x, y, rain.glyphs.glyph(id), style)?;
+        // Pad narrow glyphs out to the column width so nothing of a wider glyph that
+        // was there before is left behind.
+        let width = rain.glyphs.glyph_width(id);
+        match width < spacing {
+            true => renderer.erase(x + width, y, spacing - width),
+            false => Ok(()),
+        }
     };
-    // By subtracting height - location you get opposite location on screen.
-    let offset = match direction {
-        Down | Right => 0,
-        Up | Left => rain.height,
+    let drops = match rain.repaint {
+        true => Either::Left(0..rain.locations.len()),
+        false => Either::Right(rain.queue.iter().copied()),
     };
+    for drop in drops {
+        for row in rain.rows(drop) {
+            draw_cell(drop, row, rain.color(drop, row))?;
+        }
+    }
+    // Only the glyphs that changed, the rest of their drop is already on screen.
+    if !rain.repaint {
+        for &(drop, row) in rain.mutated.iter() {
+            draw_cell(drop, row, rain.color(drop, row))?;
+        }
+    }
+    // This Deletes old tail character of rain.
+    for drop in rain.queue.iter().copied() {
+        let (location, length) = (rain.locations[drop], rain.length[drop]);
+        if location >= length && location - length <= height {
+            let (x, y) = direction.to_screen(
+                rain.column(drop) as u16,
+                (location - length) as u16,
+                rain.height,
+                spacing,
+            );
+            renderer.erase(x, y, spacing)?;
+        }
+    }
+    Ok(())
+}
 
-    // -------------------------------------
-
-    let (mut chr, mut col, mut len, mut clr);
-    let height = rain.height as usize;
-    for row in rain.queue.iter() {
-        // character
-        chr = &rain.charaters[*row];
-        // location
-        col = &rain.locations[*row];
-        // length
-        len = &rain.length[*row];
-        // color
-        clr = &rain.colors[*row];
-
-        let start = col.saturating_sub(*len).clamp(0, chr.len());
-        let end = (col + 1).clamp(1, chr.len());
-        let slice = chr[start..end].iter();
-
-        let cstart = if col > len {
-            clr.len().saturating_sub(slice.len())
-        } else {
-            0
-        };
+#[cfg(test)]
+mod tests {
+    use crate::{Characters, Direction, FrameBuffer, RainEngine, SpeedRange, UserSettings};
+    use std::time::Duration;
 
-        let color = &clr[cstart..];
+    const WIDTH: u16 = 24;
+    const HEIGHT: u16 = 10;
 
-        for (y, (ch, _c)) in slice.rev().zip(color.iter().copied()).enumerate() {
-            queue!(
-                w,
-                move_to(
-                    *row as u16 * spacing,
-                    (*col.min(&height) - y) as u16,
-                    offset
-                ),
-                style::SetForegroundColor(_c),
-                style::Print(ch),
-            )?;
+    #[test]
+    fn rows_move_the_way_the_rain_falls() {
+        // Column 1, rows 2 and 3, a screen 10 rows tall and glyphs 2 cells wide.
+        let cases = [
+            (Direction::Down, (2, 2), (2, 3)),
+            (Direction::Up, (2, 8), (2, 7)),
+            (Direction::Right, (4, 1), (6, 1)),
+            (Direction::Left, (16, 1), (14, 1)),
+        ];
+        for (direction, row2, row3) in cases {
+            assert_eq!(direction.to_screen(1, 2, 10, 2), row2, "{direction}");
+            assert_eq!(direction.to_screen(1, 3, 10, 2), row3, "{direction}");
         }
-        // This Deletes old tail character of rain.
-        if col >= len {
-            queue!(
-                w,
-                move_to(
-                    *row as u16 * spacing,
-                    col.saturating_sub(*len) as u16,
-                    offset
-                ),
-                style::Print(" ".repeat(spacing as usize)),
-            )?;
+    }
+
+    #[test]
+    fn draws_every_visible_glyph_and_erases_tails() {
+        let directions = [
+            Direction::Up,
+            Direction::Down,
+            Direction::Left,
+            Direction::Right,
+        ];
+        for direction in directions {
+            for density in [None, Some(1.0)] {
+                let settings = UserSettings::default()
+                    .group(Characters::Bin)
+                    .direction(direction)
+                    .density(density)
+                    .speed(SpeedRange::new(10, 30).expect("valid speed"))
+                    .seed(Some(1));
+                let mut engine = RainEngine::new(settings, WIDTH, HEIGHT);
+                let mut screen = FrameBuffer::new(WIDTH, HEIGHT);
+                for step in 0..200 {
+                    engine.step(engine.now() + Duration::from_millis(10));
+                    engine
+                        .render(&mut screen)
+                        .expect("frame buffers never fail");
+                    let rain = engine.rain();
+                    let mut visible = 0;
+                    for drop in 0..rain.locations.len() {
+                        let column = rain.column(drop) as u16;
+                        for (row, glyph, _) in rain.visible(drop) {
+                            let (x, y) = direction.to_screen(column, row as u16, rain.height, 1);
+                            if x < WIDTH && y < HEIGHT {
+                                visible += 1;
+                                assert_eq!(
+                                    screen.get(x, y).map(|(glyph, _)| glyph),
+                                    Some(glyph),
+                                    "{direction} {density:?} step {step} at {x},{y}"
+                                );
+                            }
+                        }
+                    }
+                    let drawn = (0..WIDTH)
+                        .flat_map(|x| (0..HEIGHT).map(move |y| (x, y)))
+                        .filter(|&(x, y)| screen.get(x, y).is_some())
+                        .count();
+                    assert_eq!(
+                        drawn, visible,
+                        "{direction} {density:?} step {step} left tails behind"
+                    );
+                }
+            }
         }
     }
-    Ok(())
 }
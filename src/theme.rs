@@ -0,0 +1,67 @@
+use crate::config::Settings;
+use clap::ValueEnum;
+
+/// Named bundles of colors, characters, shading and speed.
+#[derive(Debug, ValueEnum, Clone, Copy, PartialEq, Eq)]
+pub enum Theme {
+    Classic,
+    Resurrections,
+    AmberCrt,
+    Ice,
+    Blood,
+    EmojiParty,
+}
+
+impl std::fmt::Display for Theme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            Self::Classic => "classic",
+            Self::Resurrections => "resurrections",
+            Self::AmberCrt => "amber-crt",
+            Self::Ice => "ice",
+            Self::Blood => "blood",
+            Self::EmojiParty => "emoji-party",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+impl Theme {
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::Classic => "Shaded green Japanese characters, just like the film",
+            Self::Resurrections => "Teal rain with a pale head from the fourth film",
+            Self::AmberCrt => "Old amber monochrome monitor",
+            Self::Ice => "Cold light blue capitals",
+            Self::Blood => "Dark red rain with a bright red head",
+            Self::EmojiParty => "Slow falling emojis",
+        }
+    }
+
+    pub fn settings(&self) -> Settings {
+        let (chars, color, head, shade, speed) = match self {
+            Self::Classic => ("jap", "0,255,65", "255,255,255", true, "40..200"),
+            Self::Resurrections => ("jap", "0,200,170", "200,255,245", true, "30..150"),
+            Self::AmberCrt => ("alphanum", "255,176,0", "255,230,160", true, "60..220"),
+            Self::Ice => ("alphaup", "120,200,255", "255,255,255", true, "50..250"),
+            Self::Blood => ("jap", "170,0,0", "255,60,60", true, "50..250"),
+            Self::EmojiParty => ("emojis", "255,255,255", "255,255,255", false, "80..300"),
+        };
+        Settings {
+            shade: Some(shade),
+            chars: Some(chars.into()),
+            color: Some(color.into()),
+            head: Some(head.into()),
+            speed: Some(speed.into()),
+            ..Settings::default()
+        }
+    }
+
+    /// One line per theme with its description, for `--list-themes`.
+    pub fn list() -> String {
+        Self::value_variants()
+            .iter()
+            .map(|theme| format!("{:<14} - {}\n", theme.to_string(), theme.description()))
+            .collect()
+    }
+}
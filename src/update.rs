@@ -1,41 +1,141 @@
-use crate::cli::Cli;
-use crate::{gen, style, thread_rng, Rain, Rng};
-use itertools::izip;
+use crate::gen::{self, ColorFn};
+use crate::{Rain, Rng, UserSettings};
 use std::time::{Duration, Instant};
 
-pub fn update(rain: &mut Rain) {
+/// Moves every drop that is due at `now` one row, then works out when it moves next.
+pub fn update<R: Rng>(rain: &mut Rain, settings: &UserSettings, rng: &mut R, now: Instant) {
     rain.queue.clear();
-    let now = Instant::now();
-    for (idx, ((time, delay), location)) in izip!(&mut rain.time, &mut rain.locations).enumerate() {
-        if *time <= now {
-            *time += *delay;
-            *location += 1;
-            rain.queue.push(idx);
+    for drop in 0..rain.locations.len() {
+        let (time, delay) = rain.time[drop];
+        // Waiting drops are started by `reset`.
+        if time > now || rain.length[drop] == 0 {
+            continue;
+        }
+        rain.time[drop].0 += gen::step_delay(
+            delay,
+            rain.locations[drop],
+            settings.gravity,
+            settings.jitter,
+            rng,
+        );
+        // A drop that caught up with the one below it waits behind it.
+        if rain.clearance(drop, rain.locations[drop]) > 1 {
+            rain.locations[drop] += 1;
+            rain.queue.push(drop);
         }
     }
 }
 
-pub fn reset<F>(create_color: F, rain: &mut Rain, settings: &Cli)
-where
-    F: Fn(style::Color, style::Color, u8) -> Vec<style::Color>,
-{
-    let mut rng = thread_rng();
-    let h16 = rain.height;
+/// Starts the drops that fell off screen over again, or with a density sets them
+/// waiting and starts the ones that have waited long enough.
+pub fn reset<R: Rng>(
+    create_color: &ColorFn,
+    rain: &mut Rain,
+    settings: &UserSettings,
+    rng: &mut R,
+    now: Instant,
+) {
     let hsize = rain.height as usize;
-    let now = Instant::now();
-    for i in rain.queue.iter() {
-        if rain.locations[*i] > hsize + rain.length[*i] {
-            rain.charaters[*i] = gen::create_drop_chars(h16, &settings.chars);
-            rain.locations[*i] = 0;
-            rain.length[*i] = rng.gen_range(4..hsize.saturating_sub(10).max(5));
-            rain.colors[*i] = create_color(
-                settings.rain_color().into(),
-                settings.head_color().into(),
-                rain.length[*i] as u8,
-            );
-            rain.time[*i] = (
-                now,
-                Duration::from_millis(rng.gen_range(settings.speed_range())),
+    for i in 0..rain.queue.len() {
+        let drop = rain.queue[i];
+        if rain.locations[drop] > hsize + rain.length[drop] {
+            match settings.density {
+                Some(density) => {
+                    rain.locations[drop] = 0;
+                    rain.length[drop] = 0;
+                    let fall = gen::fall_time(hsize, settings);
+                    rain.time[drop].0 = now + gen::spawn_wait(fall, rain.slots, density, rng);
+                }
+                None => restart(create_color, rain, drop, settings, rng, now),
+            }
+        }
+    }
+    if settings.density.is_some() {
+        for drop in 0..rain.locations.len() {
+            if rain.length[drop] == 0 && rain.time[drop].0 <= now && rain.clearance(drop, 0) > 0 {
+                restart(create_color, rain, drop, settings, rng, now);
+                rain.queue.push(drop);
+            }
+        }
+        // Waiting drops have nothing on screen to draw or erase.
+        let length = &rain.length;
+        rain.queue.retain(|&drop| length[drop] > 0);
+    }
+}
+
+fn restart<R: Rng>(
+    create_color: &ColorFn,
+    rain: &mut Rain,
+    drop: usize,
+    settings: &UserSettings,
+    rng: &mut R,
+    now: Instant,
+) {
+    let hsize = rain.height as usize;
+    let group = rain.groups[rain.column(drop)];
+    rain.glyphs.fill(&mut rain.charaters[drop], group, rng);
+    rain.locations[drop] = 0;
+    rain.length[drop] = gen::length(hsize, settings.length, rng);
+    rain.colors[drop] = create_color(rain.length[drop]);
+    rain.time[drop] = (
+        now,
+        Duration::from_millis(settings.speed_dist.sample(settings.speed, rng)),
+    );
+}
+
+/// Swaps each visible glyph for a new one with a chance of `rate`, in sequence mode
+/// the glyph moves on to the next one of its group.
+pub fn mutate<R: Rng>(rain: &mut Rain, settings: &UserSettings, rng: &mut R) {
+    rain.mutated.clear();
+    if settings.mutate <= 0.0 {
+        return;
+    }
+    for drop in 0..rain.locations.len() {
+        let group = rain.groups[rain.column(drop)];
+        for row in rain.rows(drop) {
+            if rng.gen_bool(settings.mutate) {
+                let glyph = &mut rain.charaters[drop][row];
+                *glyph = rain.glyphs.mutate(*glyph, group, rng);
+                rain.mutated.push((drop, row));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Characters, RainEngine, UserSettings};
+    use std::time::Duration;
+
+    #[test]
+    fn density_keeps_about_that_share_of_columns_busy() {
+        for density in [0.1, 0.3, 0.5, 0.7, 0.9] {
+            let settings = UserSettings::default()
+                .group(Characters::Bin)
+                .density(Some(density))
+                .seed(Some(7));
+            let mut engine = RainEngine::new(settings, 80, 30);
+            let (mut busy, mut seen) = (0, 0);
+            for step in 0..2000 {
+                engine.step(engine.now() + Duration::from_millis(50));
+                // Let the drops that start together at first spread out.
+                if step < 200 {
+                    continue;
+                }
+                let rain = engine.rain();
+                let mut columns = vec![false; rain.locations.len() / rain.slots];
+                for drop in 0..rain.locations.len() {
+                    if rain.length[drop] > 0 && *rain.rows(drop).start() < rain.height as usize {
+                        columns[rain.column(drop)] = true;
+                    }
+                }
+                busy += columns.iter().filter(|&&busy| busy).count();
+                seen += columns.len();
+            }
+            let share = busy as f64 / seen as f64;
+            assert!(
+                (share - density).abs() < 0.1,
+                "--density {density} kept {share:.2} of the columns busy"
             );
         }
     }
@@ -1,20 +1,16 @@
-use crate::cli::Cli;
-use crate::{clear, Rain};
-use crossterm::{event, style};
-use std::io::Stdout;
+use crossterm::event;
+use rusty_rain::{RainEngine, Renderer};
 use std::time::Duration;
 
-pub fn user_input(
-    stdout: &mut Stdout,
-    rain: &mut Rain,
-    settings: &Cli,
-    create_color: fn(style::Color, style::Color, u8) -> Vec<style::Color>,
-) -> std::io::Result<bool> {
+pub fn user_input<R: Renderer>(renderer: &mut R, engine: &mut RainEngine) -> std::io::Result<bool> {
     if event::poll(Duration::from_millis(50))? {
         match event::read()? {
-            event::Event::Key(keyevent) => {
+            event::Event::Key(keyevent)
                 if keyevent
-                    == event::KeyEvent::new(event::KeyCode::Char('c'), event::KeyModifiers::CONTROL)
+                    == event::KeyEvent::new(
+                        event::KeyCode::Char('c'),
+                        event::KeyModifiers::CONTROL,
+                    )
                     || keyevent
                         == event::KeyEvent::new(event::KeyCode::Esc, event::KeyModifiers::NONE)
                     || keyevent
@@ -26,14 +22,13 @@ pub fn user_input(
                         == event::KeyEvent::new(
                             event::KeyCode::Char('q'),
                             event::KeyModifiers::NONE,
-                        )
-                {
-                    return Ok(false);
-                }
+                        ) =>
+            {
+                return Ok(false);
             }
             event::Event::Resize(w, h) => {
-                clear(stdout)?;
-                *rain = Rain::new(create_color, w, h, settings);
+                renderer.clear()?;
+                engine.resize(w, h);
             }
             _ => {}
         }
@@ -1,31 +1,203 @@
-use super::*;
+use crate::{
+    Background, CharacterSet, Characters, Color, ColorDepth, Direction, Easing, Gradient,
+    LengthRange, SpeedDist, SpeedRange,
+};
+use std::collections::HashMap;
+use std::time::Duration;
 
+/// Everything the rain needs to know to be generated.
+///
+/// Built with chained setters starting from `UserSettings::default()`.
 #[derive(Debug, Clone)]
 pub struct UserSettings {
-    pub rain_color: (u8, u8, u8),
-    pub head_color: (u8, u8, u8),
-    pub group: Characters,
+    pub rain_color: Color,
+    pub head_color: Color,
+    pub group: CharacterSet,
+    pub per_column: bool,
+    pub sequence: bool,
+    pub glyph_widths: HashMap<String, u16>,
     pub shading: bool,
-    pub speed: (u64, u64),
+    pub speed: SpeedRange,
+    pub speed_dist: SpeedDist,
+    pub gravity: f64,
+    pub jitter: f64,
+    pub length: Option<LengthRange>,
     pub direction: Direction,
+    pub seed: Option<u64>,
+    pub gradient: Option<Gradient>,
+    pub easing: Easing,
+    pub rainbow: bool,
+    pub hue_cycle: Option<Duration>,
+    pub color_depth: ColorDepth,
+    pub dither: bool,
+    pub background: Background,
+    pub mutate: f64,
+    pub density: Option<f64>,
 }
 
-impl UserSettings {
-    pub fn new(
-        rain_color: (u8, u8, u8),
-        head_color: (u8, u8, u8),
-        group: Characters,
-        shading: bool,
-        speed: (u64, u64),
-        direction: Direction,
-    ) -> Self {
+impl Default for UserSettings {
+    fn default() -> Self {
         Self {
-            rain_color,
-            head_color,
-            group,
-            shading,
-            speed,
-            direction,
+            rain_color: Color::new(0, 255, 0),
+            head_color: Color::new(255, 255, 255),
+            group: CharacterSet::Builtin(Characters::Bin),
+            per_column: false,
+            sequence: false,
+            glyph_widths: HashMap::new(),
+            shading: false,
+            speed: SpeedRange::default(),
+            speed_dist: SpeedDist::default(),
+            gravity: 0.0,
+            jitter: 0.0,
+            length: None,
+            direction: Direction::Down,
+            seed: None,
+            gradient: None,
+            easing: Easing::default(),
+            rainbow: false,
+            hue_cycle: None,
+            color_depth: ColorDepth::default(),
+            dither: false,
+            background: Background::default(),
+            mutate: 0.0,
+            density: None,
         }
     }
 }
+
+impl UserSettings {
+    pub fn rain_color(mut self, rain_color: Color) -> Self {
+        self.rain_color = rain_color;
+        self
+    }
+
+    pub fn head_color(mut self, head_color: Color) -> Self {
+        self.head_color = head_color;
+        self
+    }
+
+    /// A built in `Characters` group or a `CustomChars` set.
+    pub fn group(mut self, group: impl Into<CharacterSet>) -> Self {
+        self.group = group.into();
+        self
+    }
+
+    /// Every column sticks to one group of a `Mix`, picked by weight.
+    pub fn per_column(mut self, per_column: bool) -> Self {
+        self.per_column = per_column;
+        self
+    }
+
+    /// Drops step through their group in order, for groups like `Moon` and `Clock`.
+    pub fn sequence(mut self, sequence: bool) -> Self {
+        self.sequence = sequence;
+        self
+    }
+
+    /// Widths measured on the terminal, used instead of the Unicode tables for these glyphs.
+    pub fn glyph_widths(mut self, widths: HashMap<String, u16>) -> Self {
+        self.glyph_widths = widths;
+        self
+    }
+
+    pub fn shading(mut self, shading: bool) -> Self {
+        self.shading = shading;
+        self
+    }
+
+    /// Colors the whole drop, head included, replacing the rain and head colors.
+    pub fn gradient(mut self, gradient: Option<Gradient>) -> Self {
+        self.gradient = gradient;
+        self
+    }
+
+    /// Curve used to move along the gradient or shading of a trail.
+    pub fn easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    pub fn speed(mut self, speed: SpeedRange) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// How delays are picked from the speed range.
+    pub fn speed_dist(mut self, speed_dist: SpeedDist) -> Self {
+        self.speed_dist = speed_dist;
+        self
+    }
+
+    /// How much faster drops get for every row they fall, `0.0` keeps them steady.
+    pub fn gravity(mut self, gravity: f64) -> Self {
+        self.gravity = gravity.max(0.0);
+        self
+    }
+
+    /// Fraction from `0.0` to `1.0` that each step of a drop can be stretched or shrunk by.
+    pub fn jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Trail lengths of the drops, `None` runs from 4 up to 10 rows short of the screen.
+    pub fn length(mut self, length: Option<LengthRange>) -> Self {
+        self.length = length;
+        self
+    }
+
+    /// Gives each column its own hue spread around the color wheel.
+    pub fn rainbow(mut self, rainbow: bool) -> Self {
+        self.rainbow = rainbow;
+        self
+    }
+
+    /// Turns every color once around the color wheel each `period`.
+    pub fn hue_cycle(mut self, period: Option<Duration>) -> Self {
+        self.hue_cycle = period;
+        self
+    }
+
+    /// Squeezes colors into what the terminal can show, `Mono` uses bold and dim.
+    pub fn color_depth(mut self, color_depth: ColorDepth) -> Self {
+        self.color_depth = color_depth;
+        self
+    }
+
+    /// Dithers trails when the color depth is less than truecolor.
+    pub fn dither(mut self, dither: bool) -> Self {
+        self.dither = dither;
+        self
+    }
+
+    /// On a `Light` background colors are flipped from dark to light so they still stand out.
+    pub fn background(mut self, background: Background) -> Self {
+        self.background = background;
+        self
+    }
+
+    /// Chance from `0.0` to `1.0` that each visible glyph changes every step.
+    pub fn mutate(mut self, rate: f64) -> Self {
+        self.mutate = rate.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Lets every column hold several drops. About `density` of the columns are busy
+    /// at a time, from sparse at `0.1` to a downpour at `1.0`. `None` keeps one drop
+    /// per column that starts over as soon as it leaves the screen.
+    pub fn density(mut self, density: Option<f64>) -> Self {
+        self.density = density.map(|density| density.clamp(f64::EPSILON, 1.0));
+        self
+    }
+
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Seeds the random number generator so runs can be repeated.
+    pub fn seed(mut self, seed: Option<u64>) -> Self {
+        self.seed = seed;
+        self
+    }
+}